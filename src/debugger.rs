@@ -0,0 +1,350 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::io::BufRead;
+use std::rc::Rc;
+
+use anyhow::Result;
+
+use crate::interpreter::{DebugHook, Interpreter};
+use crate::output::{with_diagnostics, Output};
+use crate::parser::Parser;
+use crate::scanner::Scanner;
+use crate::stmt::Stmt;
+use crate::types::LitVal;
+use crate::Lox;
+
+impl Lox {
+    /// Runs the script at `path` under the debugger, which pauses before its
+    /// first statement and reads commands from `commands`.
+    pub fn debug_file(&mut self, path: &str, commands: impl BufRead + 'static) -> Result<()> {
+        let source = std::fs::read_to_string(path)?;
+        let debugger = Debugger {
+            lines: source.lines().map(str::to_string).collect(),
+            commands: Box::new(commands),
+            output: Rc::clone(&self.interpreter.output),
+            breakpoints: BTreeSet::new(),
+            mode: Mode::StepIn,
+            line: 0,
+            ran: Vec::new(),
+            frame_lines: Vec::new(),
+        };
+        self.interpreter.debug_hook = Some(Box::new(debugger));
+        self.path = Some(path.to_string());
+        let result = self.run(source);
+        self.interpreter.debug_hook = None;
+        result
+    }
+}
+
+/// When to pause next, besides at breakpoints.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    Continue,
+    /// At the next statement.
+    StepIn,
+    /// At the next statement no deeper in the call stack than this.
+    StepOver(usize),
+    /// At the next statement shallower in the call stack than this.
+    StepOut(usize),
+}
+
+struct Debugger {
+    /// The script's source, for showing where it's paused.
+    lines: Vec<String>,
+    commands: Box<dyn BufRead>,
+    output: Output,
+    breakpoints: BTreeSet<usize>,
+    mode: Mode,
+    /// The line of the last statement run, and the positions of the statements
+    /// run on it since it was reached. A breakpoint on a line holding several statements pauses
+    /// once, but again whenever a loop comes back around to one of them.
+    line: usize,
+    ran: Vec<(usize, usize)>,
+    /// The line each call stack frame is at, the script's first.
+    frame_lines: Vec<usize>,
+}
+
+impl DebugHook for Debugger {
+    fn before(&mut self, interpreter: &mut Interpreter, stmt: &Stmt) {
+        // A block is only somewhere to stop once its first statement runs.
+        if matches!(stmt, Stmt::Block { .. }) {
+            return;
+        }
+        let line = stmt.line();
+        let depth = interpreter.call_stack().len();
+        self.frame_lines.resize(depth + 1, line);
+        self.frame_lines[depth] = line;
+
+        let position = stmt.position();
+        let arrived = self.line != line || self.ran.contains(&position);
+        if arrived {
+            self.line = line;
+            self.ran.clear();
+        }
+        self.ran.push(position);
+        let at_breakpoint = arrived && self.breakpoints.contains(&line);
+        let pause = at_breakpoint
+            || match self.mode {
+                Mode::Continue => false,
+                Mode::StepIn => true,
+                Mode::StepOver(from) => depth <= from,
+                Mode::StepOut(from) => depth < from,
+            };
+        if pause {
+            self.pause(interpreter, line, depth);
+        }
+    }
+}
+
+impl Debugger {
+    fn say(&self, args: fmt::Arguments) {
+        let _ = writeln!(self.output.borrow_mut(), "{}", args);
+    }
+
+    /// Shows where the program is and runs commands until one resumes it.
+    fn pause(&mut self, interpreter: &mut Interpreter, line: usize, depth: usize) {
+        let text = self.lines.get(line - 1).map_or("", |text| text.trim());
+        self.say(format_args!("Stopped at line {}: {}", line, text));
+        loop {
+            let _ = write!(self.output.borrow_mut(), "(debug) ");
+            let _ = self.output.borrow_mut().flush();
+            let mut command = String::new();
+            if !matches!(self.commands.read_line(&mut command), Ok(n) if n > 0) {
+                // Out of commands: let the program finish.
+                self.say(format_args!(""));
+                self.breakpoints.clear();
+                self.mode = Mode::Continue;
+                return;
+            }
+            let command = command.trim();
+            let (name, argument) = command.split_once(' ').unwrap_or((command, ""));
+            let argument = argument.trim();
+            self.mode = match name {
+                "c" | "continue" => Mode::Continue,
+                "s" | "step" => Mode::StepIn,
+                "n" | "next" => Mode::StepOver(depth),
+                "o" | "out" => Mode::StepOut(depth),
+                "b" | "break" => {
+                    self.set_breakpoint(argument, true);
+                    continue;
+                }
+                "d" | "delete" => {
+                    self.set_breakpoint(argument, false);
+                    continue;
+                }
+                "p" | "print" => {
+                    self.print(interpreter, argument);
+                    continue;
+                }
+                "l" | "locals" => {
+                    self.locals(interpreter);
+                    continue;
+                }
+                "bt" | "backtrace" => {
+                    self.backtrace(interpreter);
+                    continue;
+                }
+                "" => continue,
+                "h" | "help" => {
+                    self.say(format_args!("{}", HELP));
+                    continue;
+                }
+                _ => {
+                    self.say(format_args!(
+                        "Unknown command '{}'. Type help for a list.",
+                        name
+                    ));
+                    continue;
+                }
+            };
+            return;
+        }
+    }
+
+    /// Adds or removes the breakpoint on line `argument`; without one, lists them.
+    fn set_breakpoint(&mut self, argument: &str, add: bool) {
+        if argument.is_empty() {
+            let lines: Vec<String> = self.breakpoints.iter().map(usize::to_string).collect();
+            self.say(format_args!("Breakpoints: {}", lines.join(", ")));
+            return;
+        }
+        match argument.parse::<usize>() {
+            Ok(line) if add => {
+                self.breakpoints.insert(line);
+                self.say(format_args!("Breakpoint set at line {}.", line));
+            }
+            Ok(line) if self.breakpoints.remove(&line) => {
+                self.say(format_args!("Breakpoint at line {} deleted.", line))
+            }
+            Ok(line) => self.say(format_args!("No breakpoint at line {}.", line)),
+            Err(_) => self.say(format_args!("Expected a line number.")),
+        }
+    }
+
+    /// Evaluates `source` in the paused scope, reporting errors alongside the output.
+    fn print(&mut self, interpreter: &mut Interpreter, source: &str) {
+        let output = Rc::clone(&self.output);
+        let expr = with_diagnostics(&output, || {
+            let mut scanner = Scanner::new(source.to_string());
+            let tokens = scanner.scan_tokens().clone();
+            Parser::new(tokens).parse_expression().ok()
+        });
+        let Some(expr) = expr else { return };
+        let env = interpreter.env();
+        match interpreter.evaluate_in(&expr, env) {
+            Ok(value) => self.say(format_args!("{}", value)),
+            Err(e) => self.say(format_args!("Error: {}", e.message)),
+        }
+    }
+
+    /// Prints the variables of each scope up to the globals, innermost first.
+    /// At the top level, prints the script's globals instead, leaving out the
+    /// native functions and constants.
+    fn locals(&self, interpreter: &Interpreter) {
+        let mut env = Some(interpreter.env());
+        let at_top = Rc::ptr_eq(&interpreter.env(), &interpreter.globals);
+        while let Some(scope) = env {
+            if !at_top && Rc::ptr_eq(&scope, &interpreter.globals) {
+                break;
+            }
+            let scope = scope.borrow();
+            let mut names: Vec<_> = scope.values().iter().collect();
+            names.sort_by(|a, b| a.0.cmp(b.0));
+            for (name, value) in names {
+                if !matches!(value, LitVal::Native(_)) && !scope.is_constant(name) {
+                    self.say(format_args!("{} = {}", name, value));
+                }
+            }
+            env = scope.enclosing.clone();
+        }
+    }
+
+    /// Prints the call stack, innermost first, with the line each frame is at.
+    fn backtrace(&self, interpreter: &Interpreter) {
+        let frames = interpreter.call_stack();
+        for (i, line) in self.frame_lines.iter().enumerate().rev() {
+            let name = match i {
+                0 => "script",
+                _ => &frames[i - 1],
+            };
+            self.say(format_args!(
+                "#{} {} at line {}",
+                frames.len() - i,
+                name,
+                line
+            ));
+        }
+    }
+}
+
+const HELP: &str = "\
+break <line>   Pause before running <line>; without <line>, list breakpoints.
+delete <line>  Remove the breakpoint on <line>.
+continue       Run to the next breakpoint.
+step           Run to the next statement, entering calls.
+next           Run to the next statement, stepping over calls.
+out            Run until the current function returns.
+print <expr>   Evaluate <expr> where the program is paused.
+locals         Show the variables in scope.
+backtrace      Show the functions being called.
+Each command can be shortened to its first letter, or bt for backtrace.";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_util::TempDir, Buffer};
+
+    fn debug(name: &str, source: &str, commands: &str) -> String {
+        let dir = TempDir::new(&format!("debug_{}", name));
+        let path = dir.join("script.lox");
+        std::fs::write(&path, source).unwrap();
+
+        let out = Buffer::default();
+        let mut lox = Lox::new();
+        lox.set_output(out.clone());
+        lox.set_diagnostics(out.clone());
+        lox.debug_file(
+            path.to_str().unwrap(),
+            std::io::Cursor::new(commands.to_string()),
+        )
+        .unwrap();
+        out.contents()
+    }
+
+    const SCRIPT: &str = "fun square(n) {
+  var result = n * n;
+  return result;
+}
+var a = 3;
+var b = square(a);
+print b;
+";
+
+    #[test]
+    fn breakpoints_and_inspection() {
+        let output = debug(
+            "breakpoints",
+            SCRIPT,
+            "break 3\ncontinue\nlocals\nbacktrace\nprint result + 1\nprint nope\nout\nlocals\nc\n",
+        );
+        assert_eq!(
+            output,
+            "Stopped at line 1: fun square(n) {
+(debug) Breakpoint set at line 3.
+(debug) Stopped at line 3: return result;
+(debug) n = 3
+result = 9
+(debug) #0 square at line 3
+#1 script at line 6
+(debug) 10
+(debug) Error: Undefined variable 'nope'.
+(debug) Stopped at line 7: print b;
+(debug) a = 3
+b = 9
+square = <fn square>
+(debug) 9
+"
+        );
+
+        let output = debug(
+            "loop",
+            "var i = 0;\nwhile (i < 3)\n  i = i + 1;\nprint \"done\";\n",
+            "break 3\nbreak 4\nc\nc\nc\nc\nprint i\nc\n",
+        );
+        assert_eq!(
+            output,
+            "Stopped at line 1: var i = 0;
+(debug) Breakpoint set at line 3.
+(debug) Breakpoint set at line 4.
+(debug) Stopped at line 3: i = i + 1;
+(debug) Stopped at line 3: i = i + 1;
+(debug) Stopped at line 3: i = i + 1;
+(debug) Stopped at line 4: print \"done\";
+(debug) 3
+(debug) done
+"
+        );
+    }
+
+    #[test]
+    fn stepping() {
+        let output = debug("stepping", SCRIPT, "n\nn\ns\ns\nnope\ns\n");
+        let stops: Vec<&str> = output
+            .lines()
+            .filter_map(|line| line.strip_prefix("(debug) Stopped at line "))
+            .collect();
+        assert_eq!(
+            stops,
+            [
+                "5: var a = 3;",
+                "6: var b = square(a);",
+                "2: var result = n * n;",
+                "3: return result;",
+                "7: print b;"
+            ]
+        );
+        assert!(output.contains("Unknown command 'nope'. Type help for a list."));
+        // Running out of commands lets the program finish.
+        assert!(output.ends_with("(debug) \n9\n"));
+    }
+}
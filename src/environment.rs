@@ -1,11 +1,18 @@
 use anyhow::Result;
-use std::{cell::RefCell, cmp::Ordering, collections::HashMap, rc::Rc};
+use std::{
+    cell::RefCell,
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
 
 use crate::{interpreter::RuntimeError, token::Token, types::LitVal};
 
 #[derive(Debug, Clone)]
 pub struct Environment {
     values: HashMap<String, LitVal>,
+    /// Names bound with `define_constant`, which `assign` won't change.
+    constants: HashSet<String>,
     pub enclosing: Option<Rc<RefCell<Environment>>>,
 }
 
@@ -13,6 +20,7 @@ impl Environment {
     pub fn new() -> Self {
         Environment {
             values: HashMap::new(),
+            constants: HashSet::new(),
             enclosing: None,
         }
     }
@@ -20,6 +28,7 @@ impl Environment {
     pub fn new_with_enclosing(enclosing: Rc<RefCell<Environment>>) -> Self {
         Environment {
             values: HashMap::new(),
+            constants: HashSet::new(),
             enclosing: Some(enclosing),
         }
     }
@@ -38,7 +47,12 @@ impl Environment {
     }
 
     pub fn assign(&mut self, name: &Token, value: &LitVal) -> Result<LitVal, RuntimeError> {
-        if self.values.contains_key(&name.lexeme) {
+        if self.constants.contains(&name.lexeme) {
+            Err(RuntimeError::new(
+                name.clone(),
+                &format!("Can't assign to constant '{}'.", name.lexeme),
+            ))
+        } else if self.values.contains_key(&name.lexeme) {
             self.values.insert(name.lexeme.clone(), value.clone());
             Ok(value.clone())
         } else if let Some(enc) = &self.enclosing {
@@ -51,9 +65,28 @@ impl Environment {
         }
     }
 
+    /// Binds `name`, replacing any earlier binding in this scope, even a
+    /// constant one.
     pub fn define(&mut self, name: String, value: LitVal) {
+        self.constants.remove(&name);
+        self.values.insert(name, value);
+    }
+
+    /// Binds `name` to a value that assignment can't change.
+    pub fn define_constant(&mut self, name: String, value: LitVal) {
+        self.constants.insert(name.clone());
         self.values.insert(name, value);
     }
+
+    /// Whether `name` is bound in this scope with `define_constant`.
+    pub fn is_constant(&self, name: &str) -> bool {
+        self.constants.contains(name)
+    }
+
+    /// The bindings made directly in this scope, ignoring enclosing ones.
+    pub fn values(&self) -> &HashMap<String, LitVal> {
+        &self.values
+    }
 }
 
 impl PartialEq for Environment {
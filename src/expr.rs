@@ -1,4 +1,10 @@
-use crate::{token::Token, types::LitVal};
+use std::rc::Rc;
+
+use crate::{
+    stmt::{Param, Stmt},
+    token::Token,
+    types::LitVal,
+};
 
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub enum Expr {
@@ -11,14 +17,48 @@ pub enum Expr {
         name: Token,
         value: Box<Expr>,
     },
+    /// `target op= value`, where `op` is the `+=`, `-=`, `*=` or `/=` token.
+    CompoundAssign {
+        target: Box<Expr>,
+        op: Token,
+        value: Box<Expr>,
+    },
+    /// `++target`, `--target`, `target++` or `target--`.
+    Increment {
+        target: Box<Expr>,
+        op: Token,
+        prefix: bool,
+    },
     Call {
         callee: Box<Expr>,
         paren: Token,
         arguments: Vec<Expr>,
     },
+    /// `object.name`.
+    Get {
+        object: Box<Expr>,
+        name: Token,
+    },
     Grouping {
         expression: Box<Expr>,
     },
+    /// `object[index]`; `bracket` is the '[' token, for error reporting.
+    Index {
+        object: Box<Expr>,
+        bracket: Token,
+        index: Box<Expr>,
+    },
+    /// `object[index] = value`.
+    SetIndex {
+        object: Box<Expr>,
+        bracket: Token,
+        index: Box<Expr>,
+        value: Box<Expr>,
+    },
+    /// An anonymous function: a `Stmt::Function` named `anonymous`, built
+    /// once by the parser and positioned at the `fun` or `=>` token.
+    Lambda(Rc<Stmt>),
+    List(Vec<Expr>),
     Literal(LitVal),
     Logical {
         left: Box<Expr>,
@@ -33,9 +73,36 @@ pub enum Expr {
 }
 
 impl Expr {
+    /// The line the expression starts on, unless it's made only of literals.
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            Expr::Binary { left, op, .. } | Expr::Logical { left, op, .. } => {
+                left.line().or(Some(op.line))
+            }
+            Expr::Assign { name, .. } | Expr::Variable(name) => Some(name.line),
+            Expr::CompoundAssign { target, .. } => target.line(),
+            Expr::Increment { target, op, prefix } => {
+                if *prefix {
+                    Some(op.line)
+                } else {
+                    target.line()
+                }
+            }
+            Expr::Call { callee, paren, .. } => callee.line().or(Some(paren.line)),
+            Expr::Get { object, .. } => object.line(),
+            Expr::Grouping { expression } => expression.line(),
+            Expr::Index { object, .. } | Expr::SetIndex { object, .. } => object.line(),
+            Expr::Lambda(function) => Some(function.line()),
+            Expr::List(items) => items.iter().find_map(Expr::line),
+            Expr::Literal(_) => None,
+            Expr::Unary { op, .. } => Some(op.line),
+        }
+    }
+
+    /// Prints the expression as an S-expression, e.g. `(+ 1 (group 2))`.
     pub fn stringify(&self) -> String {
         match self {
-            Expr::Binary { left, op, right } => {
+            Expr::Binary { left, op, right } | Expr::Logical { left, op, right } => {
                 format!("({} {} {})", op.type_, left.stringify(), right.stringify())
             }
             Expr::Grouping { expression } => format!("(group {})", expression.stringify()),
@@ -47,24 +114,81 @@ impl Expr {
                         format!("{}", n)
                     }
                 }
-                LitVal::String(s) => s.to_string(),
-                LitVal::Bool(b) => b.to_string(),
-                LitVal::Nil => "nil".to_string(),
-                LitVal::NotExist => todo!(),
-                LitVal::Function(lox_function) => todo!(),
-                LitVal::Clock(clock) => todo!(),
+                LitVal::String(s) => format!("{:?}", s),
+                _ => lit_val.to_string(),
             },
             Expr::Unary { op, right } => {
                 format!("({} {})", op.type_, right.stringify())
             }
-            Expr::Variable(token) => todo!(),
-            Expr::Assign { name, value } => todo!(),
-            Expr::Logical { left, op, right } => todo!(),
+            Expr::Variable(name) => name.lexeme.clone(),
+            Expr::Assign { name, value } => format!("(= {} {})", name.lexeme, value.stringify()),
+            Expr::CompoundAssign { target, op, value } => {
+                format!(
+                    "({} {} {})",
+                    op.type_,
+                    target.stringify(),
+                    value.stringify()
+                )
+            }
+            Expr::Increment { target, op, prefix } => format!(
+                "({}{} {})",
+                if *prefix { "pre" } else { "post" },
+                op.type_,
+                target.stringify()
+            ),
+            Expr::Lambda(function) => match function.as_ref() {
+                Stmt::Function { params, body, .. } => format!(
+                    "(fun {}{})",
+                    stringify_params(params),
+                    body.iter()
+                        .map(|stmt| format!(" {}", stmt.stringify()))
+                        .collect::<String>()
+                ),
+                _ => unreachable!("lambdas are always functions"),
+            },
+            Expr::List(items) => parenthesize("list", items),
+            Expr::Get { object, name } => format!("(. {} {})", object.stringify(), name.lexeme),
+            Expr::Index { object, index, .. } => {
+                format!("([] {} {})", object.stringify(), index.stringify())
+            }
+            Expr::SetIndex {
+                object,
+                index,
+                value,
+                ..
+            } => format!(
+                "([]= {} {} {})",
+                object.stringify(),
+                index.stringify(),
+                value.stringify()
+            ),
             Expr::Call {
-                callee,
-                paren,
-                arguments,
-            } => todo!(),
+                callee, arguments, ..
+            } => parenthesize(&format!("call {}", callee.stringify()), arguments),
         }
     }
 }
+
+/// `(name a b ...)`, or `(name)` if there are no arguments.
+fn parenthesize(name: &str, exprs: &[Expr]) -> String {
+    let mut s = format!("({}", name);
+    for expr in exprs {
+        s.push(' ');
+        s.push_str(&expr.stringify());
+    }
+    s.push(')');
+    s
+}
+
+/// `(a (= b 1) (... rest))`, the parameter list of a function.
+pub fn stringify_params(params: &[Param]) -> String {
+    let params: Vec<String> = params
+        .iter()
+        .map(|param| match (&param.default, param.rest) {
+            (_, true) => format!("(... {})", param.name.lexeme),
+            (Some(default), false) => format!("(= {} {})", param.name.lexeme, default.stringify()),
+            (None, false) => param.name.lexeme.clone(),
+        })
+        .collect();
+    format!("({})", params.join(" "))
+}
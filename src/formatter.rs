@@ -0,0 +1,395 @@
+use crate::output::diagnostic;
+use crate::scanner::Scanner;
+use crate::token::{Token, TokenType};
+
+use TokenType::{
+    Bang, Catch, Comma, Comment, Dot, DotDotDot, Else, Eof, False, Finally, Identifier, LeftBrace,
+    LeftBracket, LeftParen, Minus, MinusMinus, Nil, Plus, PlusPlus, RightBrace, RightBracket,
+    RightParen, Semicolon, Super, This, Tilde, True,
+};
+
+const INDENT: &str = "  ";
+const MAX_WIDTH: usize = 80;
+
+/// One output line: tokens at an indentation level, with an optional trailing
+/// comment. A line with neither is blank.
+#[derive(Debug, Default)]
+struct Line {
+    indent: usize,
+    tokens: Vec<Token>,
+    comment: Option<String>,
+}
+
+/// Reformats `source`, keeping every token and comment. Returns `None` if it
+/// doesn't scan, after reporting why.
+///
+/// Formatting works on the token stream rather than the syntax tree, so
+/// comments survive and even code with parse errors can be formatted.
+pub fn format_source(source: &str) -> Option<String> {
+    let mut scanner = Scanner::with_comments(source.to_string());
+    let tokens = scanner.scan_tokens().clone();
+    if scanner.had_error {
+        return None;
+    }
+
+    let mut formatted = String::new();
+    for line in split_lines(&tokens) {
+        render(&line, &mut formatted);
+    }
+
+    // A safety net: the output must scan to exactly the same tokens.
+    let mut rescanned = Scanner::with_comments(formatted.clone());
+    let same = rescanned.scan_tokens().len() == tokens.len()
+        && rescanned
+            .scan_tokens()
+            .iter()
+            .zip(&tokens)
+            .all(|(a, b)| a.type_ == b.type_ && a.lexeme.trim_end() == b.lexeme.trim_end());
+    if !same {
+        diagnostic(format_args!(
+            "Formatting would change the program, so it was left alone."
+        ));
+        return None;
+    }
+    Some(formatted)
+}
+
+/// The line a token starts on; multi-line strings report the line they end on.
+fn start_line(token: &Token) -> usize {
+    token.line - token.lexeme.matches('\n').count()
+}
+
+fn flush(lines: &mut Vec<Line>, current: &mut Vec<Token>, indent: usize) {
+    if !current.is_empty() {
+        lines.push(Line {
+            indent,
+            tokens: std::mem::take(current),
+            comment: None,
+        });
+    }
+}
+
+/// Breaks the token stream into lines: one statement per line, with blocks
+/// indented and at most one blank line kept between statements.
+fn split_lines(tokens: &[Token]) -> Vec<Line> {
+    let mut lines: Vec<Line> = Vec::new();
+    let mut current = Vec::new();
+    let mut indent = 0;
+    // Open parens and brackets, which stop `;` from ending the line (as in
+    // `for`), saved and reset for each brace so lambda bodies format normally.
+    let mut depth: usize = 0;
+    let mut depths = Vec::new();
+    let mut last_line = 0;
+
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = &tokens[i];
+        let next = tokens.get(i + 1).map(|t| &t.type_);
+        i += 1;
+
+        let after_open_brace = lines
+            .last()
+            .and_then(|line| line.tokens.last())
+            .is_some_and(|t| t.type_ == LeftBrace);
+        if current.is_empty()
+            && last_line > 0
+            && start_line(token) > last_line + 1
+            && !after_open_brace
+            && !matches!(token.type_, RightBrace | Eof)
+        {
+            lines.push(Line::default());
+        }
+
+        match token.type_ {
+            Eof => break,
+            Comment => {
+                let text = token.lexeme.trim_end().to_string();
+                let on_same_line = start_line(token) == last_line;
+                if on_same_line && !current.is_empty() {
+                    flush(&mut lines, &mut current, indent);
+                }
+                match lines.last_mut() {
+                    Some(line) if on_same_line && line.comment.is_none() => {
+                        line.comment = Some(text)
+                    }
+                    _ => {
+                        flush(&mut lines, &mut current, indent);
+                        lines.push(Line {
+                            indent,
+                            tokens: Vec::new(),
+                            comment: Some(text),
+                        });
+                    }
+                }
+            }
+            LeftBrace if next == Some(&RightBrace) => {
+                current.push(token.clone());
+                current.push(tokens[i].clone());
+                i += 1;
+                if !next_continues_brace(tokens.get(i)) {
+                    flush(&mut lines, &mut current, indent);
+                }
+            }
+            LeftBrace => {
+                current.push(token.clone());
+                flush(&mut lines, &mut current, indent);
+                depths.push(depth);
+                depth = 0;
+                indent += 1;
+            }
+            RightBrace => {
+                flush(&mut lines, &mut current, indent);
+                indent = indent.saturating_sub(1);
+                depth = depths.pop().unwrap_or(0);
+                current.push(token.clone());
+                if !next_continues_brace(tokens.get(i)) {
+                    flush(&mut lines, &mut current, indent);
+                }
+            }
+            Semicolon => {
+                current.push(token.clone());
+                if depth == 0 {
+                    flush(&mut lines, &mut current, indent);
+                }
+            }
+            LeftParen | LeftBracket => {
+                depth += 1;
+                current.push(token.clone());
+            }
+            RightParen | RightBracket => {
+                depth = depth.saturating_sub(1);
+                current.push(token.clone());
+            }
+            _ => current.push(token.clone()),
+        }
+        last_line = token.line;
+    }
+    flush(&mut lines, &mut current, indent);
+    lines
+}
+
+/// Whether the token after a `}` belongs on the same line, as in `} else {`
+/// or the `})` closing a lambda argument.
+fn next_continues_brace(next: Option<&Token>) -> bool {
+    next.is_some_and(|t| {
+        matches!(
+            t.type_,
+            Else | Catch
+                | Finally
+                | RightParen
+                | RightBracket
+                | Comma
+                | Semicolon
+                | Dot
+                | LeftParen
+        )
+    })
+}
+
+/// Whether a token can end an operand, making a following `-` binary, a
+/// following `(` a call and a following `++` postfix.
+fn ends_operand(type_: &TokenType) -> bool {
+    matches!(
+        type_,
+        Identifier
+            | TokenType::Number
+            | TokenType::String
+            | True
+            | False
+            | Nil
+            | This
+            | Super
+            | RightParen
+            | RightBracket
+    )
+}
+
+/// Joins tokens with single spaces, except around brackets, `.`, `,`, `;`
+/// and unary operators.
+fn join(tokens: &[Token]) -> String {
+    let mut text = String::new();
+    let mut prev: Option<&TokenType> = None;
+    let mut prev_operand = false;
+    let mut prev_unary = false;
+    for token in tokens {
+        let type_ = &token.type_;
+        let postfix = matches!(type_, PlusPlus | MinusMinus) && prev_operand;
+        let unary = match type_ {
+            Bang | Tilde => true,
+            Minus | Plus | PlusPlus | MinusMinus => !prev_operand,
+            _ => false,
+        };
+        let space = match prev {
+            None => false,
+            // `- -a` and `+ +a` would scan as `--a` and `++a` if joined.
+            Some(Minus) if prev_unary => matches!(type_, Minus | MinusMinus),
+            Some(Plus) if prev_unary => matches!(type_, Plus | PlusPlus),
+            Some(LeftParen | LeftBracket | Dot | DotDotDot) => false,
+            Some(LeftBrace) if *type_ == RightBrace => false,
+            Some(_) if prev_unary => false,
+            Some(_) => {
+                !(matches!(type_, RightParen | RightBracket | Comma | Semicolon | Dot)
+                    || matches!(type_, LeftParen | LeftBracket) && prev_operand
+                    || postfix)
+            }
+        };
+        if space {
+            text.push(' ');
+        }
+        text.push_str(&token.lexeme);
+
+        prev = Some(type_);
+        prev_operand = ends_operand(type_) || postfix;
+        prev_unary = unary;
+    }
+    text
+}
+
+fn render(line: &Line, out: &mut String) {
+    let text = join(&line.tokens);
+    if text.is_empty() && line.comment.is_none() {
+        out.push('\n');
+        return;
+    }
+    let comment = match (&line.comment, text.is_empty()) {
+        (Some(comment), true) => comment.clone(),
+        (Some(comment), false) => format!(" {}", comment),
+        (None, _) => String::new(),
+    };
+    // Trailing comments don't count, since wrapping the code can't shorten them.
+    let width = INDENT.len() * line.indent + text.chars().count();
+    if width > MAX_WIDTH {
+        if let Some(lines) = wrap(line) {
+            for line in lines {
+                render(&line, out);
+            }
+            return;
+        }
+    }
+    out.push_str(&INDENT.repeat(line.indent));
+    out.push_str(&text);
+    out.push_str(&comment);
+    out.push('\n');
+}
+
+/// Splits a long line at the first call or list literal on it, putting each
+/// argument on its own line. Returns `None` if there's nothing to split.
+fn wrap(line: &Line) -> Option<Vec<Line>> {
+    let tokens = &line.tokens;
+    for open in 0..tokens.len() {
+        let after_operand = open > 0 && ends_operand(&tokens[open - 1].type_);
+        let opens = match tokens[open].type_ {
+            LeftParen => after_operand,
+            LeftBracket => !after_operand,
+            _ => false,
+        };
+        if !opens {
+            continue;
+        }
+
+        // Find the matching close, splitting the contents at top-level commas.
+        let mut depth = 0;
+        let mut arguments = vec![Vec::new()];
+        let mut close = None;
+        for (i, token) in tokens.iter().enumerate().skip(open + 1) {
+            match token.type_ {
+                LeftParen | LeftBracket | LeftBrace => depth += 1,
+                RightParen | RightBracket | RightBrace if depth == 0 => {
+                    close = Some(i);
+                    break;
+                }
+                RightParen | RightBracket | RightBrace => depth -= 1,
+                _ => (),
+            }
+            arguments.last_mut().unwrap().push(token.clone());
+            if token.type_ == Comma && depth == 0 {
+                arguments.push(Vec::new());
+            }
+        }
+        let Some(close) = close else { continue };
+        if close == open + 1 {
+            continue;
+        }
+
+        let mut lines = vec![Line {
+            indent: line.indent,
+            tokens: tokens[..=open].to_vec(),
+            comment: None,
+        }];
+        lines.extend(arguments.into_iter().map(|tokens| Line {
+            indent: line.indent + 1,
+            tokens,
+            comment: None,
+        }));
+        lines.push(Line {
+            indent: line.indent,
+            tokens: tokens[close..].to_vec(),
+            comment: line.comment.clone(),
+        });
+        return Some(lines);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format() {
+        let source = "// Header.
+var a=1+-2;   var b = !a;
+
+
+fun f(x,y=2,...z){
+  // Leading.
+  if(x>y){return x++ - -y;}else{ return [1,2][0]; } // Trailing.
+}
+for(var i=0;i<3;i=i+1)print i;
+try{throw \"e\";}catch(e){}finally{print f((a)=>a*2);}
+var g = fun () {
+    return nil;
+};
+";
+        let expected = "// Header.
+var a = 1 + -2;
+var b = !a;
+
+fun f(x, y = 2, ...z) {
+  // Leading.
+  if (x > y) {
+    return x++ - -y;
+  } else {
+    return [1, 2][0];
+  } // Trailing.
+}
+for (var i = 0; i < 3; i = i + 1) print i;
+try {
+  throw \"e\";
+} catch (e) {} finally {
+  print f((a) => a * 2);
+}
+var g = fun () {
+  return nil;
+};
+";
+        let formatted = format_source(source).unwrap();
+        assert_eq!(formatted, expected);
+        assert_eq!(format_source(&formatted).unwrap(), expected);
+    }
+
+    #[test]
+    fn wrapping() {
+        let source =
+            "print some_function(first_argument, [1, 2, 3], another_function(a, b), last_one);";
+        let expected = "print some_function(
+  first_argument,
+  [1, 2, 3],
+  another_function(a, b),
+  last_one
+);
+";
+        assert_eq!(format_source(source).unwrap(), expected);
+        assert_eq!(format_source(expected).unwrap(), expected);
+    }
+}
@@ -0,0 +1,205 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::output::Buffer;
+use crate::{FsAccess, Lox};
+
+/// The outcome of running a directory of golden-file tests.
+#[derive(Debug, Default)]
+pub struct TestReport {
+    pub passed: usize,
+    /// Each failing script, with a description of every mismatch.
+    pub failures: Vec<(PathBuf, Vec<String>)>,
+}
+
+/// What a script expects, gathered from comments in the style of the Crafting
+/// Interpreters test suite.
+#[derive(Debug, Default, PartialEq)]
+struct Expectations {
+    /// `// expect: value`
+    output: Vec<String>,
+    /// `// Error at ...` and `// [line N] Error ...` (or `// [java line N]`),
+    /// or the two lines printed for `// expect runtime error: message`.
+    diagnostics: Vec<String>,
+}
+
+fn parse_expectations(source: &str) -> Expectations {
+    let mut expectations = Expectations::default();
+    let mut in_string = false;
+    for (i, line) in source.lines().enumerate() {
+        let line_number = i + 1;
+        let Some(start) = comment_start(line, &mut in_string) else {
+            continue;
+        };
+        // Markers can sit further into the comment, as in the suite's
+        // `// // expect runtime error: ...`.
+        let comment = &line[start..];
+        let after = |marker: &str| comment.find(marker).map(|i| &comment[i + marker.len()..]);
+        if let Some(value) = after("// expect: ") {
+            expectations.output.push(value.to_string());
+        } else if let Some(message) = after("// expect runtime error: ") {
+            expectations.diagnostics.push(message.to_string());
+            expectations
+                .diagnostics
+                .push(format!("[line {}]", line_number));
+        } else if let Some(error) = after("// Error") {
+            expectations
+                .diagnostics
+                .push(format!("[line {}] Error{}", line_number, error));
+        } else if let Some(error) = after("// [line ").or_else(|| after("// [java line ")) {
+            expectations.diagnostics.push(format!("[line {}", error));
+        }
+    }
+    expectations
+}
+
+/// Where the comment on `line` starts: its first `//` outside a string
+/// literal. Strings can span lines, so `in_string` carries over between calls.
+fn comment_start(line: &str, in_string: &mut bool) -> Option<usize> {
+    for (i, c) in line.char_indices() {
+        if c == '"' {
+            *in_string = !*in_string;
+        } else if !*in_string && line[i..].starts_with("//") {
+            return Some(i);
+        }
+    }
+    None
+}
+
+/// Compares two sequences of lines position by position.
+fn diff(what: &str, expected: &[String], actual: &[String]) -> Vec<String> {
+    let mut mismatches = Vec::new();
+    for i in 0..expected.len().max(actual.len()) {
+        match (expected.get(i), actual.get(i)) {
+            (Some(e), Some(a)) if e == a => (),
+            (Some(e), Some(a)) => mismatches.push(format!(
+                "{} line {}: expected '{}', got '{}'.",
+                what,
+                i + 1,
+                e,
+                a
+            )),
+            (Some(e), None) => mismatches.push(format!("Missing {}: '{}'.", what, e)),
+            (None, Some(a)) => mismatches.push(format!("Unexpected {}: '{}'.", what, a)),
+            (None, None) => unreachable!(),
+        }
+    }
+    mismatches
+}
+
+/// Runs one script, returning its mismatches against its expectations.
+fn run_test(path: &Path) -> io::Result<Vec<String>> {
+    let source = fs::read_to_string(path)?;
+    let expectations = parse_expectations(&source);
+
+    let (output, diagnostics) = (Buffer::default(), Buffer::default());
+    let mut lox = Lox::new();
+    lox.set_output(output.clone());
+    lox.set_diagnostics(diagnostics.clone());
+    lox.set_input(io::empty());
+    lox.set_fs_access(FsAccess {
+        roots: path.parent().map(Path::to_path_buf).into_iter().collect(),
+        read_only: true,
+    });
+    lox.path = Some(path.to_string_lossy().into_owned());
+    // Errors are checked through the diagnostics they print.
+    let _ = lox.run(source);
+
+    let lines = |buffer: Buffer| {
+        buffer
+            .contents()
+            .lines()
+            .map(String::from)
+            .collect::<Vec<_>>()
+    };
+    let mut mismatches = diff("output", &expectations.output, &lines(output));
+    mismatches.extend(diff(
+        "error",
+        &expectations.diagnostics,
+        &lines(diagnostics),
+    ));
+    Ok(mismatches)
+}
+
+fn find_scripts(dir: &Path, scripts: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            find_scripts(&path, scripts)?;
+        } else if path.extension().is_some_and(|ext| ext == "lox") {
+            scripts.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Runs every `.lox` file under `dir`, in path order.
+pub fn run_tests(dir: &Path) -> io::Result<TestReport> {
+    let mut scripts = Vec::new();
+    find_scripts(dir, &mut scripts)?;
+    scripts.sort();
+
+    let mut report = TestReport::default();
+    for script in scripts {
+        let mismatches = run_test(&script)?;
+        if mismatches.is_empty() {
+            report.passed += 1;
+        } else {
+            report.failures.push((script, mismatches));
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expectations() {
+        let source = "print 1; // expect: 1
+print \"a//b\"; // expect: a//b
+var x = ; // Error at ';': Expect expression.
+// [line 9] Error at end: Expect '}' after block.
+-nil; // expect runtime error: Operand must be a number.
+var s = \"a // Error at x\";
+var t = \"
+// expect: not a comment\"; // [java line 8] Error: Unterminated string.
+print 2 // expect: 2 // expect: ignored
+";
+        assert_eq!(
+            parse_expectations(source),
+            Expectations {
+                output: vec![
+                    "1".to_string(),
+                    "a//b".to_string(),
+                    "2 // expect: ignored".to_string()
+                ],
+                diagnostics: vec![
+                    "[line 3] Error at ';': Expect expression.".to_string(),
+                    "[line 9] Error at end: Expect '}' after block.".to_string(),
+                    "Operand must be a number.".to_string(),
+                    "[line 5]".to_string(),
+                    "[line 8] Error: Unterminated string.".to_string(),
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn mismatches() {
+        let lines = |s: &str| s.lines().map(String::from).collect::<Vec<_>>();
+        assert_eq!(
+            diff("output", &lines("1\n2\n3"), &lines("1\n4")),
+            vec![
+                "output line 2: expected '2', got '4'.",
+                "Missing output: '3'."
+            ]
+        );
+        assert_eq!(
+            diff("error", &[], &lines("oops")),
+            vec!["Unexpected error: 'oops'."]
+        );
+    }
+}
@@ -1,26 +1,91 @@
+use crate::natives::{self, FsAccess, Input};
+use crate::output::{self, Output};
+use crate::parser::Parser;
+use crate::scanner::Scanner;
 use crate::token::Token;
-use crate::types::{Clock, LoxCallable, LoxFunction};
+use crate::types::{Int, List, LoxCallable, LoxError, LoxFunction, LoxModule};
 use crate::Lox;
 use crate::{environment::Environment, expr::Expr, stmt::Stmt, token::TokenType, types::LitVal};
 use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
 pub struct Interpreter {
     pub globals: Rc<RefCell<Environment>>,
     env: Rc<RefCell<Environment>>,
+    /// The file being executed, which imports are resolved against.
+    path: Option<PathBuf>,
+    /// Modules that finished loading, keyed by canonical path.
+    modules: HashMap<PathBuf, LoxModule>,
+    /// Modules being loaded, outermost first, as canonical and display paths.
+    loading: Vec<(PathBuf, String)>,
+    /// What the file natives may touch.
+    pub fs_access: FsAccess,
+    /// Where the input natives read from; the process's stdin if `None`.
+    pub input: Option<Input>,
+    /// Where `print` writes.
+    pub output: Output,
+    /// Called before each statement runs, as by the debugger.
+    pub debug_hook: Option<Box<dyn DebugHook>>,
+    /// The names of the Lox functions being called, outermost first.
+    call_stack: Vec<String>,
+}
+
+/// Something that watches a program run, a statement at a time.
+pub trait DebugHook {
+    /// Called before `stmt` runs. The hook is detached meanwhile, so code it
+    /// evaluates doesn't reenter it.
+    fn before(&mut self, interpreter: &mut Interpreter, stmt: &Stmt);
 }
 
 #[derive(Debug)]
 pub struct RuntimeError {
-    pub token: Token,
+    pub token: Box<Token>,
     pub message: String,
+    /// The value carried by a `throw` or `return`, if that's what this is.
+    pub value: Option<Box<LitVal>>,
 }
 
 impl RuntimeError {
     pub fn new(token: Token, message: &str) -> Self {
         RuntimeError {
-            token,
+            token: Box::new(token),
             message: message.to_string(),
+            value: None,
+        }
+    }
+
+    /// `return` unwinds to the enclosing call as a `RuntimeError` that
+    /// `try` can't catch, carrying the returned value.
+    pub fn return_value(value: LitVal) -> Self {
+        let token = Token {
+            type_: TokenType::Return,
+            lexeme: "".to_string(),
+            literal: LitVal::Nil,
+            line: 0,
+            column: 0,
+        };
+        RuntimeError {
+            token: Box::new(token),
+            message: "return".to_string(),
+            value: Some(Box::new(value)),
+        }
+    }
+
+    pub fn is_return(&self) -> bool {
+        self.token.type_ == TokenType::Return && self.message == "return"
+    }
+
+    /// The value a `catch` clause binds: whatever was thrown, or an error value
+    /// describing a built-in failure.
+    pub fn caught_value(self) -> LitVal {
+        match self.value {
+            Some(value) => *value,
+            None => LitVal::Error(LoxError {
+                message: self.message,
+                line: self.token.line,
+            }),
         }
     }
 }
@@ -28,8 +93,9 @@ impl RuntimeError {
 impl From<anyhow::Error> for RuntimeError {
     fn from(error: anyhow::Error) -> Self {
         RuntimeError {
-            token: Token::default(), // may need to adjust this to provide a meaningful token
+            token: Box::default(), // may need to adjust this to provide a meaningful token
             message: error.to_string(),
+            value: None,
         }
     }
 }
@@ -37,14 +103,38 @@ impl From<anyhow::Error> for RuntimeError {
 impl Interpreter {
     pub fn new() -> Self {
         let globals = Rc::new(RefCell::new(Environment::new()));
-        globals
-            .borrow_mut()
-            .define("clock".to_string(), LitVal::Clock(Clock));
+        natives::define_globals(&mut globals.borrow_mut());
 
         Interpreter {
             globals: Rc::clone(&globals),
             env: Rc::clone(&globals),
+            path: None,
+            modules: HashMap::new(),
+            loading: Vec::new(),
+            fs_access: FsAccess::default(),
+            input: None,
+            output: output::stdout(),
+            debug_hook: None,
+            call_stack: Vec::new(),
+        }
+    }
+
+    /// The innermost scope, which is `globals` between statements.
+    pub fn env(&self) -> Rc<RefCell<Environment>> {
+        Rc::clone(&self.env)
+    }
+
+    /// The names of the Lox functions being called, outermost first.
+    pub fn call_stack(&self) -> &[String] {
+        &self.call_stack
+    }
+
+    /// Sets the script being run, so its imports resolve relative to it.
+    pub fn set_path(&mut self, path: &str) {
+        if let Ok(canonical) = Path::new(path).canonicalize() {
+            self.loading = vec![(canonical, path.to_string())];
         }
+        self.path = Some(PathBuf::from(path));
     }
 
     pub fn interpret(&mut self, stmts: Vec<Stmt>) {
@@ -56,12 +146,31 @@ impl Interpreter {
         }
     }
 
+    /// Runs `stmts` as a script, which stops at its first uncaught runtime error.
+    pub fn interpret_script(&mut self, stmts: Vec<Stmt>) {
+        for stmt in stmts {
+            if let Err(e) = self.execute(&stmt) {
+                Lox::runtime_error(e);
+                return;
+            }
+        }
+    }
+
     fn execute(&mut self, stmt: &Stmt) -> Result<LitVal, RuntimeError> {
+        if let Some(mut hook) = self.debug_hook.take() {
+            hook.before(self, stmt);
+            self.debug_hook = Some(hook);
+        }
         match stmt {
-            Stmt::Expr(expr) => self.evaluate(expr),
-            Stmt::Print(expr) => {
+            Stmt::Expr { expr, .. } => self.evaluate(expr),
+            Stmt::Print {
+                keyword,
+                value: expr,
+            } => {
                 let value = self.evaluate(expr)?;
-                println!("{}", value);
+                writeln!(self.output.borrow_mut(), "{}", value).map_err(|e| {
+                    RuntimeError::new(keyword.clone(), &format!("Could not write output: {}.", e))
+                })?;
                 Ok(value)
             }
             Stmt::Var { name, initializer } => {
@@ -75,7 +184,7 @@ impl Interpreter {
                     .define(name.lexeme.clone(), value.clone());
                 Ok(value)
             }
-            Stmt::Block(stmts) => self.exec_block(
+            Stmt::Block { stmts, .. } => self.exec_block(
                 stmts,
                 Rc::new(RefCell::new(Environment::new_with_enclosing(Rc::clone(
                     &self.env,
@@ -85,6 +194,7 @@ impl Interpreter {
                 condition,
                 then_branch,
                 else_branch,
+                ..
             } => {
                 if is_truthy(&self.evaluate(condition)?) {
                     self.execute(then_branch)
@@ -94,7 +204,9 @@ impl Interpreter {
                     Ok(LitVal::Nil)
                 }
             }
-            Stmt::While { condition, body } => {
+            Stmt::While {
+                condition, body, ..
+            } => {
                 while is_truthy(&self.evaluate(condition)?) {
                     self.execute(body)?;
                 }
@@ -115,27 +227,175 @@ impl Interpreter {
 
                 Ok(LitVal::Nil)
             }
-            Stmt::Return {
+            Stmt::Import {
                 keyword,
+                path,
+                alias,
+            } => {
+                let module = self.import(keyword, path)?;
+                match alias {
+                    Some(alias) => self
+                        .env
+                        .borrow_mut()
+                        .define(alias.lexeme.clone(), LitVal::Module(module)),
+                    None => {
+                        for (name, value) in module.env.borrow().values() {
+                            self.env.borrow_mut().define(name.clone(), value.clone());
+                        }
+                    }
+                }
+                Ok(LitVal::Nil)
+            }
+            Stmt::Return {
+                keyword: _,
                 value: stmt_value,
             } => {
                 let value = if *stmt_value == Expr::Literal(LitVal::Nil) {
                     LitVal::Nil
                 } else {
-                    match self.evaluate(stmt_value) {
-                        Ok(n) => n,
-                        Err(e) => todo!(), // todo
-                    }
+                    self.evaluate(stmt_value)?
                 };
-                let temp = Token {
-                    type_: TokenType::Return,
-                    lexeme: "".to_string(),
-                    literal: value,
-                    line: 0,
+                Err(RuntimeError::return_value(value))
+            }
+            Stmt::Throw { keyword, value } => {
+                let value = self.evaluate(value)?;
+                // Rethrowing a caught error keeps its original message and line.
+                let (message, line) = match &value {
+                    LitVal::Error(error) => (error.message.clone(), error.line),
+                    _ => (value.to_string(), keyword.line),
                 };
-                Err(RuntimeError::new(temp, "return"))
+                Err(RuntimeError {
+                    token: Box::new(Token {
+                        line,
+                        ..keyword.clone()
+                    }),
+                    message,
+                    value: Some(Box::new(value)),
+                })
+            }
+            Stmt::Try {
+                body,
+                catch,
+                finally,
+                ..
+            } => {
+                let mut result = self.exec_block(body, self.new_scope());
+                if let Some((name, catch_body)) = catch {
+                    if let Err(e) = result {
+                        if e.is_return() {
+                            result = Err(e);
+                        } else {
+                            let env = self.new_scope();
+                            env.borrow_mut()
+                                .define(name.lexeme.clone(), e.caught_value());
+                            result = self.exec_block(catch_body, env);
+                        }
+                    }
+                }
+                if let Some(finally) = finally {
+                    // An error or return in `finally` replaces whatever was unwinding.
+                    self.exec_block(finally, self.new_scope())?;
+                }
+                result
+            }
+        }
+    }
+
+    /// Loads the module at `path` relative to the current file, running it the
+    /// first time it's imported.
+    fn import(&mut self, keyword: &Token, path: &Token) -> Result<LoxModule, RuntimeError> {
+        let LitVal::String(name) = &path.literal else {
+            unreachable!("parser only accepts string module paths");
+        };
+        let dir = self
+            .path
+            .as_ref()
+            .and_then(|p| p.parent())
+            .unwrap_or(Path::new(""));
+        let resolved = dir.join(name);
+        // Modules are read like any other file, so they're held to the same roots.
+        self.fs_access
+            .check(path, &resolved.to_string_lossy(), false)
+            .map_err(|_| {
+                RuntimeError::new(
+                    path.clone(),
+                    &format!("Access to module '{}' is not allowed.", name),
+                )
+            })?;
+        let canonical = resolved.canonicalize().map_err(|e| {
+            RuntimeError::new(
+                path.clone(),
+                &format!("Could not load module '{}': {}.", name, e),
+            )
+        })?;
+
+        if let Some(module) = self.modules.get(&canonical) {
+            return Ok(module.clone());
+        }
+        if let Some(start) = self.loading.iter().position(|(p, _)| *p == canonical) {
+            let cycle: Vec<_> = self.loading[start..]
+                .iter()
+                .map(|(_, display)| display.as_str())
+                .chain([self.loading[start].1.as_str()])
+                .collect();
+            return Err(RuntimeError::new(
+                keyword.clone(),
+                &format!("Import cycle detected: {}.", cycle.join(" -> ")),
+            ));
+        }
+
+        let source = std::fs::read_to_string(&canonical).map_err(|e| {
+            RuntimeError::new(
+                path.clone(),
+                &format!("Could not load module '{}': {}.", name, e),
+            )
+        })?;
+        let mut scanner = Scanner::new(source);
+        let tokens = scanner.scan_tokens().clone();
+        let mut parser = Parser::new(tokens);
+        let stmts = parser.parse();
+        let stmts = match stmts {
+            Ok(stmts) if !scanner.had_error && !parser.had_error => stmts,
+            _ => {
+                return Err(RuntimeError::new(
+                    path.clone(),
+                    &format!("Could not compile module '{}'.", name),
+                ))
             }
+        };
+
+        // Each module gets its own globals, with the natives in an enclosing
+        // scope so that only the module's own definitions are exported.
+        let mut builtins = Environment::new();
+        natives::define_globals(&mut builtins);
+        let env = Rc::new(RefCell::new(Environment::new_with_enclosing(Rc::new(
+            RefCell::new(builtins),
+        ))));
+
+        self.loading
+            .push((canonical.clone(), resolved.to_string_lossy().into_owned()));
+        let prev_path = self.path.replace(resolved);
+        let result = self.exec_block(&stmts, Rc::clone(&env));
+        self.path = prev_path;
+        self.loading.pop();
+        match result {
+            Err(e) if e.is_return() => (),
+            Err(e) => return Err(e),
+            Ok(_) => (),
         }
+
+        let module = LoxModule {
+            name: name.clone(),
+            env,
+        };
+        self.modules.insert(canonical, module.clone());
+        Ok(module)
+    }
+
+    fn new_scope(&self) -> Rc<RefCell<Environment>> {
+        Rc::new(RefCell::new(Environment::new_with_enclosing(Rc::clone(
+            &self.env,
+        ))))
     }
 
     pub fn exec_block(
@@ -148,10 +408,8 @@ impl Interpreter {
         let mut result = Ok(LitVal::NotExist);
         for st in stmts {
             result = self.execute(st);
-            if let Err(RuntimeError { message, .. }) = &result {
-                if message == "return" {
-                    break;
-                }
+            if result.is_err() {
+                break;
             }
         }
         self.env = prev;
@@ -161,25 +419,131 @@ impl Interpreter {
     fn evaluate(&mut self, expr: &Expr) -> Result<LitVal, RuntimeError> {
         match expr {
             Expr::Binary { left, op, right } => self.eval_binary(left, op, right),
+            Expr::Get { object, name } => match self.evaluate(object)? {
+                LitVal::Module(module) => {
+                    let value = module.env.borrow().values().get(&name.lexeme).cloned();
+                    value.ok_or_else(|| {
+                        RuntimeError::new(
+                            name.clone(),
+                            &format!("Module '{}' has no export '{}'.", module.name, name.lexeme),
+                        )
+                    })
+                }
+                LitVal::Error(error) => match name.lexeme.as_str() {
+                    "message" => Ok(LitVal::String(error.message)),
+                    "line" => Ok(LitVal::Int(error.line as Int)),
+                    _ => Err(RuntimeError::new(
+                        name.clone(),
+                        &format!("Undefined property '{}'.", name.lexeme),
+                    )),
+                },
+                _ => Err(RuntimeError::new(
+                    name.clone(),
+                    "Only modules and errors have properties.",
+                )),
+            },
             Expr::Grouping { expression } => self.evaluate(expression),
             Expr::Literal(lit_val) => Ok(lit_val.clone()),
+            Expr::List(elements) => {
+                let items = elements
+                    .iter()
+                    .map(|element| self.evaluate(element))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(LitVal::list(items))
+            }
+            Expr::Index {
+                object,
+                bracket,
+                index,
+            } => {
+                let object = self.evaluate(object)?;
+                let index = self.evaluate(index)?;
+                list_get(bracket, &object, &index)
+            }
+            Expr::SetIndex {
+                object,
+                bracket,
+                index,
+                value,
+            } => {
+                let object = self.evaluate(object)?;
+                let index = self.evaluate(index)?;
+                let value = self.evaluate(value)?;
+                list_set(bracket, &object, &index, value.clone())?;
+                Ok(value)
+            }
             Expr::Unary { op, right } => {
                 let right = self.evaluate(right)?;
                 match op.type_ {
                     TokenType::Bang => Ok(LitVal::Bool(!is_truthy(&right))),
                     TokenType::Minus => match right {
+                        LitVal::Int(x) => x
+                            .checked_neg()
+                            .map(LitVal::Int)
+                            .ok_or_else(|| RuntimeError::new(op.clone(), "Integer overflow.")),
                         LitVal::Number(x) => Ok(LitVal::Number(-x)),
                         _ => Err(RuntimeError::new(op.clone(), "Operand must be a number.")),
                     },
+                    TokenType::Tilde => match right {
+                        LitVal::Int(x) => Ok(LitVal::Int(!x)),
+                        _ => Err(RuntimeError::new(op.clone(), "Operand must be an integer.")),
+                    },
                     _ => unreachable!("grammar should imply that this never happens"),
                 }
             }
             Expr::Variable(token) => self.env.borrow().get(token),
+            Expr::Lambda(function) => {
+                let function = LoxFunction::new(Rc::clone(function), Rc::clone(&self.env));
+                Ok(LitVal::Function(function))
+            }
             Expr::Assign { name, value } => {
                 let value = self.evaluate(value)?;
                 self.env.borrow_mut().assign(name, &value)?;
                 Ok(value)
             }
+            Expr::CompoundAssign { target, op, value } => {
+                // `+=` and friends apply the operator without the trailing `=`.
+                let type_ = match op.type_ {
+                    TokenType::PlusEqual => TokenType::Plus,
+                    TokenType::MinusEqual => TokenType::Minus,
+                    TokenType::StarEqual => TokenType::Star,
+                    TokenType::SlashEqual => TokenType::Slash,
+                    _ => unreachable!("parser only produces compound assignment operators"),
+                };
+                let binary_op = Token {
+                    type_,
+                    ..op.clone()
+                };
+                self.update(target, |interpreter, current| {
+                    let value = interpreter.evaluate(value)?;
+                    let result = interpreter.binary_op(&binary_op, current, value)?;
+                    Ok((result.clone(), result))
+                })
+            }
+            Expr::Increment { target, op, prefix } => {
+                let delta = if op.type_ == TokenType::PlusPlus {
+                    1
+                } else {
+                    -1
+                };
+                self.update(target, |_, current| {
+                    let result = match current {
+                        LitVal::Int(i) => i
+                            .checked_add(delta)
+                            .map(LitVal::Int)
+                            .ok_or_else(|| RuntimeError::new(op.clone(), "Integer overflow."))?,
+                        LitVal::Number(n) => LitVal::Number(n + delta as f64),
+                        _ => {
+                            return Err(RuntimeError::new(op.clone(), "Operand must be a number."))
+                        }
+                    };
+                    if *prefix {
+                        Ok((result.clone(), result))
+                    } else {
+                        Ok((result, current))
+                    }
+                })
+            }
             Expr::Logical { left, op, right } => {
                 let left = self.evaluate(left)?;
                 if op.type_ == TokenType::Or {
@@ -202,25 +566,77 @@ impl Interpreter {
                     .map(|arg| self.evaluate(arg))
                     .collect::<Result<Vec<_>, _>>()?;
 
-                if let LitVal::Function(function) = callee {
-                    if arguments.len() != function.arity() {
+                let function: &dyn LoxCallable = match &callee {
+                    LitVal::Function(function) => function,
+                    LitVal::Native(native) => native,
+                    _ => {
                         return Err(RuntimeError::new(
                             paren.clone(),
-                            &format!(
-                                "Expected {} arguments but got {}.",
-                                function.arity(),
-                                arguments.len()
-                            ),
-                        ));
+                            "Can only call functions and classes.",
+                        ))
                     }
-                    function.call(self, arguments)
-                } else {
-                    Err(RuntimeError::new(
+                };
+                if !function.arity().accepts(arguments.len()) {
+                    return Err(RuntimeError::new(
                         paren.clone(),
-                        "Can only call functions and classes.",
-                    ))
+                        &format!(
+                            "Expected {} arguments but got {}.",
+                            function.arity(),
+                            arguments.len()
+                        ),
+                    ));
+                }
+                if let LitVal::Function(function) = &callee {
+                    self.call_stack.push(function.name().to_string());
+                }
+                let result = function.call(self, paren, arguments);
+                if let LitVal::Function(_) = &callee {
+                    self.call_stack.pop();
                 }
+                result
+            }
+        }
+    }
+
+    /// Evaluates `expr` as though it appeared in `env`.
+    pub fn evaluate_in(
+        &mut self,
+        expr: &Expr,
+        env: Rc<RefCell<Environment>>,
+    ) -> Result<LitVal, RuntimeError> {
+        let prev = Rc::clone(&self.env);
+        self.env = env;
+        let result = self.evaluate(expr);
+        self.env = prev;
+        result
+    }
+
+    /// Reads `target` once, stores the first value `f` returns and yields the second.
+    fn update(
+        &mut self,
+        target: &Expr,
+        f: impl FnOnce(&mut Self, LitVal) -> Result<(LitVal, LitVal), RuntimeError>,
+    ) -> Result<LitVal, RuntimeError> {
+        match target {
+            Expr::Variable(name) => {
+                let current = self.env.borrow().get(name)?;
+                let (new, result) = f(self, current)?;
+                self.env.borrow_mut().assign(name, &new)?;
+                Ok(result)
+            }
+            Expr::Index {
+                object,
+                bracket,
+                index,
+            } => {
+                let object = self.evaluate(object)?;
+                let index = self.evaluate(index)?;
+                let current = list_get(bracket, &object, &index)?;
+                let (new, result) = f(self, current)?;
+                list_set(bracket, &object, &index, new)?;
+                Ok(result)
             }
+            _ => unreachable!("parser only produces assignable targets"),
         }
     }
 
@@ -232,13 +648,16 @@ impl Interpreter {
     ) -> Result<LitVal, RuntimeError> {
         let left = self.evaluate(left)?;
         let right = self.evaluate(right)?;
+        self.binary_op(op, left, right)
+    }
 
+    fn binary_op(&self, op: &Token, left: LitVal, right: LitVal) -> Result<LitVal, RuntimeError> {
         fn check_number_operands(
             operator: &Token,
             left: &LitVal,
             right: &LitVal,
         ) -> Result<(), RuntimeError> {
-            if let (LitVal::Number(_), LitVal::Number(_)) = (left, right) {
+            if left.as_number().is_some() && right.as_number().is_some() {
                 Ok(())
             } else {
                 Err(RuntimeError::new(
@@ -249,12 +668,11 @@ impl Interpreter {
         }
         use TokenType::*;
         match op.type_ {
-            Minus | Slash | Star | Greater | GreaterEqual | Less | LessEqual => {
-                check_number_operands(op, &left, &right)?
-            }
+            Minus | Slash | Star | Percent | StarStar | TildeSlash | Greater | GreaterEqual
+            | Less | LessEqual => check_number_operands(op, &left, &right)?,
             Plus => match (&left, &right) {
-                (LitVal::Number(_), LitVal::Number(_)) => (),
-                (LitVal::String(_), LitVal::String(_)) => (),
+                (LitVal::String(a), LitVal::String(b)) => return Ok(LitVal::String(a.clone() + b)),
+                _ if left.as_number().is_some() && right.as_number().is_some() => (),
                 _ => {
                     return Err(RuntimeError::new(
                         op.clone(),
@@ -262,27 +680,169 @@ impl Interpreter {
                     ))
                 }
             },
-            _ => (),
+            Ampersand | Pipe | Caret | LessLess | GreaterGreater => {
+                let (LitVal::Int(a), LitVal::Int(b)) = (&left, &right) else {
+                    return Err(RuntimeError::new(op.clone(), "Operands must be integers."));
+                };
+                let (a, b) = (*a, *b);
+                let shift = || {
+                    u32::try_from(b)
+                        .ok()
+                        .filter(|b| *b < Int::BITS)
+                        .ok_or_else(|| {
+                            RuntimeError::new(op.clone(), "Shift amount must be between 0 and 63.")
+                        })
+                };
+                return match op.type_ {
+                    Ampersand => Ok(LitVal::Int(a & b)),
+                    Pipe => Ok(LitVal::Int(a | b)),
+                    Caret => Ok(LitVal::Int(a ^ b)),
+                    LessLess => Ok(LitVal::Int(a << shift()?)),
+                    GreaterGreater => Ok(LitVal::Int(a >> shift()?)),
+                    _ => unreachable!(),
+                };
+            }
+            BangEqual => return Ok(LitVal::Bool(!is_equal(&left, &right))),
+            EqualEqual => return Ok(LitVal::Bool(is_equal(&left, &right))),
+            _ => unreachable!(),
         };
 
+        // Two ints stay ints (except under `/` and negative `**`), anything else
+        // is widened to a float.
+        if let (LitVal::Int(a), LitVal::Int(b)) = (&left, &right) {
+            let (a, b) = (*a, *b);
+            let int_op = |result: Option<Int>| {
+                result
+                    .map(LitVal::Int)
+                    .ok_or_else(|| RuntimeError::new(op.clone(), "Integer overflow."))
+            };
+            if b == 0 && matches!(op.type_, Percent | TildeSlash) {
+                return Err(RuntimeError::new(op.clone(), "Division by zero."));
+            }
+            match op.type_ {
+                Minus => return int_op(a.checked_sub(b)),
+                Star => return int_op(a.checked_mul(b)),
+                Plus => return int_op(a.checked_add(b)),
+                Percent => return int_op(floor_mod(a, b)),
+                TildeSlash => return int_op(floor_div(a, b)),
+                StarStar if b >= 0 => {
+                    return int_op(u32::try_from(b).ok().and_then(|b| a.checked_pow(b)))
+                }
+                Greater => return Ok(LitVal::Bool(a > b)),
+                GreaterEqual => return Ok(LitVal::Bool(a >= b)),
+                Less => return Ok(LitVal::Bool(a < b)),
+                LessEqual => return Ok(LitVal::Bool(a <= b)),
+                _ => (),
+            }
+        }
+
+        let (a, b) = (left.as_number().unwrap(), right.as_number().unwrap());
         match op.type_ {
-            Minus => Ok(left - right),
-            Slash => Ok(left / right),
-            Star => Ok(left * right),
-            Plus => Ok(left + right),
-            Greater => Ok(LitVal::Bool(left > right)),
-            GreaterEqual => Ok(LitVal::Bool(left >= right)),
-            Less => Ok(LitVal::Bool(left < right)),
-            LessEqual => Ok(LitVal::Bool(left <= right)),
-            BangEqual => Ok(LitVal::Bool(left != right)),
-            EqualEqual => Ok(LitVal::Bool(left == right)),
+            Minus => Ok(LitVal::Number(a - b)),
+            Slash => Ok(LitVal::Number(a / b)),
+            Star => Ok(LitVal::Number(a * b)),
+            Plus => Ok(LitVal::Number(a + b)),
+            Percent => {
+                let r = a % b;
+                if r != 0.0 && (r < 0.0) != (b < 0.0) {
+                    Ok(LitVal::Number(r + b))
+                } else {
+                    Ok(LitVal::Number(r))
+                }
+            }
+            TildeSlash => Ok(LitVal::Number((a / b).floor())),
+            StarStar => Ok(LitVal::Number(a.powf(b))),
+            Greater => Ok(LitVal::Bool(a > b)),
+            GreaterEqual => Ok(LitVal::Bool(a >= b)),
+            Less => Ok(LitVal::Bool(a < b)),
+            LessEqual => Ok(LitVal::Bool(a <= b)),
             _ => unreachable!(),
         }
     }
 }
 
+/// Checks that `object[index]` refers to an element of a list, returning the
+/// list and the position.
+fn list_index(
+    bracket: &Token,
+    object: &LitVal,
+    index: &LitVal,
+) -> Result<(List, usize), RuntimeError> {
+    let LitVal::List(items) = object else {
+        return Err(RuntimeError::new(
+            bracket.clone(),
+            "Only lists can be indexed.",
+        ));
+    };
+    let LitVal::Int(i) = index else {
+        return Err(RuntimeError::new(
+            bracket.clone(),
+            "List index must be an integer.",
+        ));
+    };
+    let len = items.borrow().len();
+    match usize::try_from(*i) {
+        Ok(i) if i < len => Ok((items.clone(), i)),
+        _ => Err(RuntimeError::new(
+            bracket.clone(),
+            &format!("List index {} is out of range for length {}.", i, len),
+        )),
+    }
+}
+
+fn list_get(bracket: &Token, object: &LitVal, index: &LitVal) -> Result<LitVal, RuntimeError> {
+    let (items, i) = list_index(bracket, object, index)?;
+    let item = items.borrow()[i].clone();
+    Ok(item)
+}
+
+fn list_set(
+    bracket: &Token,
+    object: &LitVal,
+    index: &LitVal,
+    value: LitVal,
+) -> Result<(), RuntimeError> {
+    let (items, i) = list_index(bracket, object, index)?;
+    items.borrow_mut()[i] = value;
+    Ok(())
+}
+
+/// Division rounding toward negative infinity, to pair with `floor_mod`.
+fn floor_div(a: Int, b: Int) -> Option<Int> {
+    let q = a.checked_div(b)?;
+    if a % b != 0 && (a < 0) != (b < 0) {
+        Some(q - 1)
+    } else {
+        Some(q)
+    }
+}
+
+/// The remainder takes the sign of the divisor, as in Python, so that
+/// `a == (a ~/ b) * b + a % b` always holds.
+fn floor_mod(a: Int, b: Int) -> Option<Int> {
+    let r = a.checked_rem(b)?;
+    if r != 0 && (r < 0) != (b < 0) {
+        Some(r + b)
+    } else {
+        Some(r)
+    }
+}
+
+/// Ints and floats compare by numeric value, so `1 == 1.0`, and lists
+/// compare their elements the same way.
+fn is_equal(left: &LitVal, right: &LitVal) -> bool {
+    match (left, right) {
+        (LitVal::List(a), LitVal::List(b)) => a.eq_by(b, is_equal),
+        (LitVal::Int(a), LitVal::Int(b)) => a == b,
+        (LitVal::Int(_), LitVal::Number(_)) | (LitVal::Number(_), LitVal::Int(_)) => {
+            left.as_number() == right.as_number()
+        }
+        _ => left == right,
+    }
+}
+
 /// `false` and `nil` are falsey, and everything else is truthy
-fn is_truthy(val: &LitVal) -> bool {
+pub(crate) fn is_truthy(val: &LitVal) -> bool {
     match val {
         LitVal::Bool(b) => *b,
         LitVal::Nil => false,
@@ -292,9 +852,10 @@ fn is_truthy(val: &LitVal) -> bool {
 
 #[cfg(test)]
 mod tests {
-    use crate::{parser::Parser, scanner::Scanner, Lox};
+    use crate::{parser::Parser, scanner::Scanner, test_util::TempDir, Lox};
 
     use super::*;
+    use crate::Buffer;
 
     #[test]
     fn evaluate() {
@@ -310,25 +871,468 @@ mod tests {
             assert_eq!(val.to_string(), expected);
         }
         f("(5 - (3 - 1)) + -1", "2");
+        f("7 / 2", "3.5");
+        f("6 / 3", "2.0");
+        f("int(2.9)", "2");
+        f("7 % 3", "1");
+        f("-7 % 3", "2");
+        f("7 % -3", "-2");
+        f("-7.5 % 2", "0.5");
+        f("7 ~/ 2", "3");
+        f("-7 ~/ 2", "-4");
+        f("7.0 ~/ 2", "3.0");
+        f("2 ** 10", "1024");
+        f("2 ** -1", "0.5");
+        f("2 ** 3 ** 2", "512");
+        f("-2 ** 2", "-4");
+        f("1 + 2 * 3 % 4", "3");
+        f("12 & 10", "8");
+        f("12 | 10", "14");
+        f("12 ^ 10", "6");
+        f("~0", "-1");
+        f("1 << 4", "16");
+        f("-16 >> 2", "-4");
+        f("6 & 3 == 2", "true");
+        f("1 << 2 < 5", "true");
+        f("1 + 0.5", "1.5");
+        f("1 == 1.0", "true");
+        f("9007199254740993 + 0", "9007199254740993");
         f("\"hello \" + \"world\"", "hello world");
         f("!1", &false.to_string());
         // f("a", "b");
     }
 
+    /// Runs `program`, then evaluates `expr` in the environment it left behind.
+    fn eval_after(program: &str, expr: &str) -> String {
+        let mut scanner = Scanner::new(program.to_string());
+        let stmts = Parser::new(scanner.scan_tokens().clone()).parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        interpreter.interpret(stmts);
+
+        let mut scanner = Scanner::new(expr.to_string());
+        let expr = Parser::new(scanner.scan_tokens().clone()).expression();
+        interpreter.evaluate(&expr.unwrap()).unwrap().to_string()
+    }
+
+    #[test]
+    fn compound_assignment() {
+        assert_eq!(eval_after("var a = 1; a += 2;", "a"), "3");
+        assert_eq!(eval_after("var a = 10; a -= 2 * 3;", "a"), "4");
+        assert_eq!(eval_after("var a = 3; a *= 2;", "a *= 2"), "12");
+        assert_eq!(eval_after("var a = 3; a /= 2;", "a"), "1.5");
+        assert_eq!(eval_after("var s = \"a\"; s += \"b\";", "s"), "ab");
+        assert_eq!(eval_after("var a = 1; var b = a++;", "b + a * 10"), "21");
+        assert_eq!(eval_after("var a = 1; var b = ++a;", "b + a * 10"), "22");
+        assert_eq!(eval_after("var a = 1; var b = a--;", "b + a * 10"), "1");
+        assert_eq!(eval_after("var a = 1.5; --a;", "-a++"), "-0.5");
+    }
+
+    #[test]
+    fn lambdas() {
+        assert_eq!(
+            eval_after("var f = fun (a, b) { return a + b; };", "f(1, 2)"),
+            "3"
+        );
+        assert_eq!(eval_after("var f = (a, b) => a * b;", "f(3, 4)"), "12");
+        assert_eq!(eval_after("var f = () => { return 5; };", "f()"), "5");
+        assert_eq!(eval_after("var f = (x) => (y) => x - y;", "f(10)(3)"), "7");
+        assert_eq!(eval_after("", "(1 + 2)"), "3");
+        assert_eq!(eval_after("", "fun () {}"), "<fn anonymous>");
+        assert_eq!(
+            eval_after(
+                "fun apply(f, x) { return f(x); } var n = 2;",
+                "apply((x) => x + n, 1)"
+            ),
+            "3"
+        );
+
+        let out = Buffer::default();
+        let mut lox = Lox::new();
+        lox.set_output(out.clone());
+        let _ = lox.run("fun (x) { print x; }(1); var a = 2; print a;".to_string());
+        assert_eq!(out.contents(), "1\n2\n");
+    }
+
+    #[test]
+    fn parameters() {
+        let program = "fun f(a, b = 10, ...rest) { return [a, b, rest]; }";
+        assert_eq!(eval_after(program, "f(1)"), "[1, 10, []]");
+        assert_eq!(eval_after(program, "f(1, 2)"), "[1, 2, []]");
+        assert_eq!(eval_after(program, "f(1, 2, 3, 4)"), "[1, 2, [3, 4]]");
+        assert_eq!(eval_after("fun f(a, b = a * 2) { return b; }", "f(4)"), "8");
+        assert_eq!(
+            eval_after("var n = 0; fun f(a = n) { return a; } n = 5;", "f()"),
+            "5"
+        );
+        assert_eq!(eval_after("var f = (...xs) => xs;", "f(1, 2)"), "[1, 2]");
+        assert_eq!(eval_after("var f = (a = 1) => a;", "f()"), "1");
+    }
+
+    #[test]
+    fn lists() {
+        assert_eq!(eval_after("var xs = [1, [2, 3]];", "xs[1][0]"), "2");
+        assert_eq!(eval_after("var xs = [1, 2]; xs[0] = 5;", "xs"), "[5, 2]");
+        assert_eq!(eval_after("var xs = [1, 2]; xs[1] += 5;", "xs"), "[1, 7]");
+        assert_eq!(
+            eval_after("var xs = [1]; var i = 0; xs[i++]++;", "[xs, i]"),
+            "[[2], 1]"
+        );
+        assert_eq!(eval_after("var a = [1]; var b = a; b[0] = 2;", "a"), "[2]");
+
+        let cycles = "var a = [1, 2]; a[1] = a; var b = [1, 2]; b[1] = b;";
+        assert_eq!(eval_after(cycles, "a"), "[1, [...]]");
+        assert_eq!(
+            eval_after(cycles, "[a == a, a == b, a == [1, a]]"),
+            "[true, true, true]"
+        );
+        assert_eq!(eval_after(cycles, "a == [2, a]"), "false");
+        assert_eq!(
+            eval_after(
+                cycles,
+                "[[1] == [1.0], [[2]] != [[2.0]], [1] == [1, 2], a == [1.0, b]]"
+            ),
+            "[true, false, false, true]"
+        );
+    }
+
+    #[test]
+    fn imports() {
+        let dir = TempDir::new("imports");
+        std::fs::create_dir_all(dir.join("lib")).unwrap();
+        std::fs::write(
+            dir.join("lib/math.lox"),
+            "var loads = 0; loads = loads + 1; fun square(x) { return x * x; }",
+        )
+        .unwrap();
+        std::fs::write(dir.join("lib/a.lox"), "import \"b.lox\";").unwrap();
+        std::fs::write(dir.join("lib/b.lox"), "import \"a.lox\";").unwrap();
+        let main = dir.join("main.lox");
+
+        let run = |program: &str| {
+            let mut scanner = Scanner::new(program.to_string());
+            let stmts = Parser::new(scanner.scan_tokens().clone()).parse().unwrap();
+            let mut interpreter = Interpreter::new();
+            interpreter.set_path(main.to_str().unwrap());
+            interpreter.fs_access = FsAccess {
+                roots: vec![dir.join("lib")],
+                read_only: true,
+            };
+            stmts
+                .iter()
+                .map(|stmt| interpreter.execute(stmt))
+                .collect::<Result<Vec<_>, _>>()
+                .map(|values| values.last().unwrap().to_string())
+                .map_err(|e| e.message)
+        };
+
+        assert_eq!(
+            run("import \"lib/math.lox\"; square(3);"),
+            Ok("9".to_string())
+        );
+        assert_eq!(
+            run("import \"lib/math.lox\" as m; import \"lib/math.lox\" as n; m.loads + n.loads;"),
+            Ok("2".to_string())
+        );
+        assert_eq!(
+            run("import \"lib/math.lox\" as m; m.clock;"),
+            Err("Module 'lib/math.lox' has no export 'clock'.".to_string())
+        );
+        let cycle = run("import \"lib/a.lox\";").unwrap_err();
+        let cycle = cycle.replace(dir.to_str().unwrap(), "");
+        assert_eq!(
+            cycle,
+            "Import cycle detected: /lib/a.lox -> /lib/b.lox -> /lib/a.lox."
+        );
+        std::fs::write(dir.join("secret.lox"), "var key = 1;").unwrap();
+        assert_eq!(
+            run("import \"secret.lox\";"),
+            Err("Access to module 'secret.lox' is not allowed.".to_string())
+        );
+    }
+
+    #[test]
+    fn exceptions() {
+        assert_eq!(
+            eval_after(
+                "var r; try { throw \"x\"; r = 1; } catch (e) { r = e; }",
+                "r"
+            ),
+            "x"
+        );
+        assert_eq!(
+            eval_after(
+                "var r; fun f() { throw \"y\"; } fun g() { return f(); } try { g(); } catch (e) { r = e; }",
+                "r"
+            ),
+            "y"
+        );
+        assert_eq!(
+            eval_after(
+                "var r;\ntry { nope; } catch (e) { r = [e.message, e.line]; }",
+                "r"
+            ),
+            "[Undefined variable 'nope'., 2]"
+        );
+        assert_eq!(
+            eval_after(
+                "var r; fun f(a) {} try { f(); } catch (e) { r = e.message; }",
+                "r"
+            ),
+            "Expected 1 arguments but got 0."
+        );
+        assert_eq!(
+            eval_after(
+                "var log = 0; fun f() { try { return 1; } finally { log = 2; } } var r = f();",
+                "[r, log]"
+            ),
+            "[1, 2]"
+        );
+        assert_eq!(
+            eval_after(
+                "var log = 0; var r;
+                fun f() { try { throw 1 + 1; } finally { log = 3; } }
+                try { f(); } catch (e) { r = e; }",
+                "[r, log]"
+            ),
+            "[2, 3]"
+        );
+        assert_eq!(
+            eval_after(
+                "var r;
+                try { try { 1 < nil; } catch (e) { throw e; } } catch (e) { r = e.line; }",
+                "r"
+            ),
+            "2"
+        );
+        assert_eq!(
+            eval_after(
+                "fun f() { try { return 1; } catch (e) { return 2; } } var r = f();",
+                "r"
+            ),
+            "1"
+        );
+    }
+
+    #[test]
+    fn math_natives() {
+        let f = |expr: &str| eval_after("", expr);
+        assert_eq!(f("sqrt(16)"), "4.0");
+        assert_eq!(f("pow(2, 10)"), "1024");
+        assert_eq!(f("pow(2, 0.5) == sqrt(2)"), "true");
+        assert_eq!(f("[abs(-3), abs(-2.5)]"), "[3, 2.5]");
+        assert_eq!(
+            f("[floor(-2.5), ceil(2.1), round(2.5), round(-2.5)]"),
+            "[-3, 3, 3, -3]"
+        );
+        assert_eq!(f("[min(3, 1.5, 2), max(3, 1.5, 2), max(7)]"), "[1.5, 3, 7]");
+        assert_eq!(f("[sin(0), cos(0), tan(0)]"), "[0.0, 1.0, 0.0]");
+        assert_eq!(f("atan2(1, 1) * 4 == PI"), "true");
+        assert_eq!(f("[log(E), exp(0)]"), "[1.0, 1.0]");
+        assert_eq!(f("[INF, -INF, NAN == NAN]"), "[inf, -inf, false]");
+        assert_eq!(
+            eval_after(
+                "var r; try { sqrt(\"4\"); } catch (e) { r = e.message; }",
+                "r"
+            ),
+            "Argument must be a number."
+        );
+        assert_eq!(
+            eval_after(
+                "var r; try { floor(NAN); } catch (e) { r = e.message; }",
+                "r"
+            ),
+            "Cannot convert NaN to an integer."
+        );
+        assert_eq!(
+            eval_after(
+                "var r; try { round(1e300); } catch (e) { r = e.message; }",
+                "r"
+            ),
+            "Cannot convert 1e300 to an integer."
+        );
+        assert_eq!(
+            eval_after(
+                "var r; try { PI = 3; } catch (e) { r = e.message; }",
+                "[r, PI == atan2(1, 1) * 4]"
+            ),
+            "[Can't assign to constant 'PI'., true]"
+        );
+        assert_eq!(eval_after("fun f() { var PI = 3; return PI; }", "f()"), "3");
+    }
+
+    #[test]
+    fn string_natives() {
+        let f = |expr: &str| eval_after("", expr);
+        let error = |expr: &str| {
+            eval_after(
+                &format!("var r; try {{ {}; }} catch (e) {{ r = e.message; }}", expr),
+                "r",
+            )
+        };
+        assert_eq!(f("[len(\"héllo\"), len([1, 2])]"), "[5, 2]");
+        assert_eq!(f("substr(\"héllo\", 1, 3)"), "éll");
+        assert_eq!(f("substr(\"héllo\", 2)"), "llo");
+        assert_eq!(
+            f("[index_of(\"héllo\", \"l\"), index_of(\"a\", \"b\")]"),
+            "[2, -1]"
+        );
+        assert_eq!(f("split(\"a,b,,c\", \",\")"), "[a, b, , c]");
+        assert_eq!(f("split(\"hé\", \"\")"), "[h, é]");
+        assert_eq!(f("join([1, \"a\", nil], \"-\")"), "1-a-nil");
+        assert_eq!(f("trim(\"  hi \")"), "hi");
+        assert_eq!(f("upper(\"straße\") + lower(\"ÀB\")"), "STRASSEàb");
+        assert_eq!(f("replace(\"a-b-c\", \"-\", \"+\")"), "a+b+c");
+        assert_eq!(
+            f("[starts_with(\"abc\", \"ab\"), ends_with(\"abc\", \"b\")]"),
+            "[true, false]"
+        );
+        assert_eq!(f("repeat(\"ab\", 3)"), "ababab");
+        assert_eq!(f("char_at(\"héllo\", 1)"), "é");
+        assert_eq!(f("[ord(\"é\"), chr(233)]"), "[233, é]");
+        assert_eq!(
+            error("char_at(\"abc\", 3)"),
+            "Index 3 is out of range for length 3."
+        );
+        assert_eq!(
+            error("char_at(\"abc\", -1)"),
+            "Index -1 is out of range for length 3."
+        );
+        assert_eq!(
+            error("substr(\"abc\", 1, 5)"),
+            "Index 6 is out of range for length 3."
+        );
+        assert_eq!(
+            error("substr(\"abc\", 1, 9223372036854775807)"),
+            "Substring end out of range."
+        );
+        assert_eq!(
+            error("repeat(\"a\", -1)"),
+            "Repeat count can't be negative."
+        );
+        assert_eq!(error("upper(1)"), "Argument must be a string.");
+        assert_eq!(error("ord(\"ab\")"), "Argument must be a single character.");
+        assert_eq!(error("chr(55296)"), "55296 is not a valid code point.");
+    }
+
+    #[test]
+    fn type_natives() {
+        let f = |expr: &str| eval_after("fun g() {}", expr);
+        assert_eq!(
+            f("[type(1), type(1.5), type(\"a\"), type(true), type(nil)]"),
+            "[number, number, string, bool, nil]"
+        );
+        assert_eq!(
+            f("[type(g), type(clock), type([]), type((x) => x)]"),
+            "[function, function, list, function]"
+        );
+        assert_eq!(f("str(1) + str(2.0) + str([nil])"), "12.0[nil]");
+        assert_eq!(
+            f("[num(\" 42 \"), num(\"-1.5e2\"), num(\"abc\"), num(\"inf\"), num(\"\")]"),
+            "[42, -150.0, nil, nil, nil]"
+        );
+        assert_eq!(
+            f("[num(\"0x1F\"), num(\"-0b10\"), num(\"0o17\"), num(\"1_000\"), num(\"1_\"), num(\"5.\")]"),
+            "[31, -2, 15, 1000, nil, nil]"
+        );
+        assert_eq!(
+            f("[bool(0), bool(\"\"), bool(nil), bool(false), bool([])]"),
+            "[true, true, false, false, true]"
+        );
+    }
+
+    #[test]
+    fn input_natives() {
+        let mut scanner = Scanner::new(
+            "var a = read_line(); var b = input(); var rest = read_all(); var c = read_line();"
+                .to_string(),
+        );
+        let stmts = Parser::new(scanner.scan_tokens().clone()).parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        let input: Input = Rc::new(RefCell::new(std::io::Cursor::new(
+            "one\r\ntwo\nthree\nfour",
+        )));
+        interpreter.input = Some(input);
+        interpreter.interpret(stmts);
+
+        let mut scanner = Scanner::new("[a, b, rest, c]".to_string());
+        let expr = Parser::new(scanner.scan_tokens().clone()).expression();
+        assert_eq!(
+            interpreter.evaluate(&expr.unwrap()).unwrap().to_string(),
+            "[one, two, three\nfour, nil]"
+        );
+    }
+
     #[test]
     fn interpret() {
+        let out = Buffer::default();
         let mut lox = Lox::new();
+        lox.set_output(out.clone());
         let _ = lox.run("print true;".to_string());
         let _ = lox.run("print \"one\";".to_string());
         let _ = lox.run("print 2 + 1;".to_string());
+        assert_eq!(out.contents(), "true\none\n3\n");
     }
 
     #[test]
     fn assignment() {
+        let out = Buffer::default();
         let mut lox = Lox::new();
+        lox.set_output(out.clone());
         let s = "var a = 1;
         print a = 2;"
             .to_string();
         let _ = lox.run(s);
+        assert_eq!(out.contents(), "2\n");
+    }
+
+    #[test]
+    fn persistent_state() {
+        let out = Buffer::default();
+        let mut lox = Lox::new();
+        lox.set_output(out.clone());
+        lox.set_diagnostics(std::io::sink());
+        let _ = lox.run("var a = 1; fun f() { return a + 1; }".to_string());
+        let _ = lox.run("a = f(); print a;".to_string());
+        lox.reset();
+        let _ = lox.run("print f;".to_string());
+        let _ = lox.run("print clock != nil;".to_string());
+        assert_eq!(out.contents(), "2\ntrue\n");
+    }
+
+    #[test]
+    fn diagnostics() {
+        let (out, err) = (Buffer::default(), Buffer::default());
+        let mut lox = Lox::new();
+        lox.set_output(out.clone());
+        lox.set_diagnostics(err.clone());
+        let _ = lox.run("print 1; print -\"a\"; print 2;".to_string());
+        let _ = lox.run("print 3 +;".to_string());
+        assert_eq!(out.contents(), "1\n2\n");
+        assert_eq!(
+            err.contents(),
+            "Operand must be a number.\n[line 1]\n[line 1] Error at ';': Expect expression.\n"
+        );
+
+        // A full output buffer makes `print` fail at its own line.
+        let err = Buffer::default();
+        lox.set_output(std::io::Cursor::new([0u8; 0]));
+        lox.set_diagnostics(err.clone());
+        let _ = lox.run("var a = 1;\nprint a;".to_string());
+        assert_eq!(
+            err.contents(),
+            "Could not write output: failed to write whole buffer.\n[line 2]\n"
+        );
+    }
+
+    #[test]
+    fn scripts_stop_at_errors() {
+        let out = Buffer::default();
+        let mut lox = Lox::new();
+        lox.set_output(out.clone());
+        lox.set_diagnostics(std::io::sink());
+        lox.path = Some("script.lox".to_string());
+        let _ = lox.run("print 1; print -\"a\"; print 2;".to_string());
+        let _ = lox.run("print 3; print 4 +;".to_string());
+        assert_eq!(out.contents(), "1\n");
     }
 }
@@ -1,34 +1,126 @@
+mod debugger;
 mod environment;
 mod expr;
+mod formatter;
+mod golden;
 mod interpreter;
+mod linter;
+mod lsp;
+mod natives;
+mod output;
 mod parser;
+mod repl;
 mod scanner;
 mod stmt;
+#[cfg(test)]
+mod test_util;
 mod token;
 mod types;
 
-use core::fmt;
-use std::io::Write;
+use std::cell::RefCell;
+use std::fmt;
+use std::io::{BufRead, Write};
+use std::rc::Rc;
 
-use anyhow::{anyhow, Error, Result};
+use anyhow::{anyhow, Result};
 use interpreter::{Interpreter, RuntimeError};
+use output::{diagnostic, with_diagnostics, Output};
 use parser::Parser;
 use scanner::Scanner;
 use token::{Token, TokenType};
 
-static mut HAD_ERROR: bool = false;
+pub use formatter::format_source;
+pub use golden::{run_tests, TestReport};
+pub use linter::{lint, Warning, WARNINGS};
+pub use lsp::{run_lsp, LanguageServer};
+pub use natives::FsAccess;
+pub use output::Buffer;
+
+pub(crate) static mut HAD_ERROR: bool = false;
 static mut HAD_RUNTIME_ERROR: bool = false;
 
-pub struct Lox {}
+pub struct Lox {
+    /// The script passed to `run_file`, if any.
+    path: Option<String>,
+    /// Kept across `run` calls, so REPL lines see earlier definitions.
+    interpreter: Interpreter,
+    /// Where compile and runtime errors are reported.
+    diagnostics: Output,
+}
 
 impl Lox {
+    /// Creates an interpreter whose scripts can't touch the filesystem; see
+    /// `set_fs_access`.
     pub fn new() -> Self {
-        Lox {}
+        Lox {
+            path: None,
+            interpreter: Interpreter::new(),
+            diagnostics: output::stderr(),
+        }
+    }
+
+    /// Sets the directories the file natives may use, and whether they may write.
+    pub fn set_fs_access(&mut self, fs_access: FsAccess) {
+        self.interpreter.fs_access = fs_access;
+    }
+
+    /// Makes `input`, `read_line` and `read_all` read from `input` instead of stdin.
+    pub fn set_input(&mut self, input: impl BufRead + 'static) {
+        self.interpreter.input = Some(Rc::new(RefCell::new(input)));
+    }
+
+    /// Sends program output, such as from `print`, to `output` instead of stdout.
+    pub fn set_output(&mut self, output: impl Write + 'static) {
+        self.interpreter.output = Rc::new(RefCell::new(output));
+    }
+
+    /// Sends error reports to `diagnostics` instead of stderr.
+    pub fn set_diagnostics(&mut self, diagnostics: impl Write + 'static) {
+        self.diagnostics = Rc::new(RefCell::new(diagnostics));
+    }
+
+    /// Forgets every definition and imported module, keeping the configuration.
+    pub fn reset(&mut self) {
+        let old = std::mem::replace(&mut self.interpreter, Interpreter::new());
+        self.interpreter.fs_access = old.fs_access;
+        self.interpreter.input = old.input;
+        self.interpreter.output = old.output;
+    }
+
+    /// Prints the tokens `source` scans to, one per line. Returns whether
+    /// it scanned without errors.
+    pub fn print_tokens(&mut self, source: &str) -> bool {
+        let mut scanner = Scanner::new(source.to_string());
+        let tokens = with_diagnostics(&self.diagnostics, || scanner.scan_tokens().clone());
+        for token in tokens {
+            self.say(format_args!("{}", token));
+        }
+        !scanner.had_error
+    }
+
+    /// Prints the statements `source` parses to as S-expressions, one per
+    /// line. Returns whether it parsed without errors.
+    pub fn print_ast(&mut self, source: &str) -> bool {
+        match with_diagnostics(&self.diagnostics, || parse(source)) {
+            Some(stmts) => {
+                for stmt in stmts {
+                    self.say(format_args!("{}", stmt.stringify()));
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Writes a line where `print` output goes, for tools like the REPL.
+    fn say(&self, args: fmt::Arguments) {
+        let _ = writeln!(self.interpreter.output.borrow_mut(), "{}", args);
     }
 
     pub fn run_file(&mut self, path: &str) -> Result<()> {
         let contents = std::fs::read_to_string(path).expect("file to be readable");
-        self.run(contents);
+        self.path = Some(path.to_string());
+        self.run(contents)?;
 
         if unsafe { HAD_ERROR } {
             std::process::exit(65);
@@ -40,38 +132,35 @@ impl Lox {
         Ok(())
     }
 
-    pub fn run_prompt(&mut self) -> Result<()> {
-        loop {
-            print!("> ");
-            std::io::stdout().flush()?;
-
-            let mut line = String::new();
-            if std::io::stdin().read_line(&mut line)? > 0 {
-                if let Err(e) = self.run(line) {
-                    eprintln!("{}", e);
-                    unsafe {
-                        HAD_ERROR = true;
-                    }
-                }
-            } else {
-                break;
-            }
-        }
-
-        Ok(())
+    fn run(&mut self, source: String) -> Result<()> {
+        let diagnostics = Rc::clone(&self.diagnostics);
+        with_diagnostics(&diagnostics, || self.run_source(source))
     }
 
-    fn run(&mut self, source: String) -> Result<()> {
+    fn run_source(&mut self, source: String) -> Result<()> {
         let mut scanner = Scanner::new(source);
         let tokens = scanner.scan_tokens().clone();
 
         let mut parser = Parser::new(tokens);
         let stmts = match parser.parse() {
             Ok(it) => it,
-            Err(err) => return Err(anyhow!("parser.parse() error in lib.rs")),
+            Err(_) => return Err(anyhow!("parser.parse() error in lib.rs")),
         };
-        let mut interpreter = Interpreter::new();
-        interpreter.interpret(stmts);
+        // The errors have been reported; running a broken program would only add noise.
+        if scanner.had_error || parser.had_error {
+            return Ok(());
+        }
+        if let Some(path) = &self.path {
+            self.interpreter.set_path(path);
+        }
+        // Like jlox, a script stops at its first runtime error rather than
+        // running on in a state its author didn't plan for. The REPL carries
+        // on with the rest of an entry.
+        if self.path.is_some() {
+            self.interpreter.interpret_script(stmts);
+        } else {
+            self.interpreter.interpret(stmts);
+        }
 
         Ok(())
     }
@@ -81,7 +170,10 @@ impl Lox {
     }
 
     pub fn runtime_error(error: RuntimeError) {
-        eprintln!("{}\n[line {}]", error.message, error.token.line);
+        diagnostic(format_args!(
+            "{}\n[line {}]",
+            error.message, error.token.line
+        ));
         unsafe {
             HAD_RUNTIME_ERROR = true;
         };
@@ -97,11 +189,20 @@ impl Lox {
     }
 
     fn report(line: usize, location: &str, message: &str) {
-        eprintln!("[line {line}] Error{location}: {message}");
+        diagnostic(format_args!("[line {line}] Error{location}: {message}"));
         unsafe { HAD_ERROR = true }
     }
 }
 
+/// Parses `source`, or returns `None` if it has errors, which have been reported.
+fn parse(source: &str) -> Option<Vec<stmt::Stmt>> {
+    let mut scanner = Scanner::new(source.to_string());
+    let tokens = scanner.scan_tokens().clone();
+    let mut parser = Parser::new(tokens);
+    let stmts = parser.parse().ok()?;
+    (!scanner.had_error && !parser.had_error).then_some(stmts)
+}
+
 impl Default for Lox {
     fn default() -> Self {
         Self::new()
@@ -0,0 +1,514 @@
+use std::collections::HashMap;
+
+use crate::expr::Expr;
+use crate::parser::Parser;
+use crate::scanner::Scanner;
+use crate::stmt::{Param, Stmt};
+use crate::token::{Token, TokenType};
+use crate::types::Arity;
+
+/// The names used to enable and disable each kind of warning.
+pub const WARNINGS: &[&str] = &[
+    "unused",
+    "unreachable",
+    "shadow",
+    "assign-in-condition",
+    "self-compare",
+    "arity",
+];
+
+/// A likely mistake found by `lint`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    pub line: usize,
+    /// One of `WARNINGS`.
+    pub name: &'static str,
+    pub message: String,
+}
+
+/// Checks `source` for likely mistakes, or returns `None` if it doesn't parse,
+/// after reporting why.
+///
+/// A `// lint: disable <name>...` comment turns warnings off from its line
+/// on, and `// lint: enable <name>...` turns them back on; `all` names every
+/// warning.
+pub fn lint(source: &str) -> Option<Vec<Warning>> {
+    let mut scanner = Scanner::with_comments(source.to_string());
+    let tokens = scanner.scan_tokens().clone();
+    let (comments, tokens): (Vec<Token>, Vec<Token>) = tokens
+        .into_iter()
+        .partition(|token| token.type_ == TokenType::Comment);
+    let mut parser = Parser::new(tokens);
+    let stmts = parser.parse().ok()?;
+    if scanner.had_error || parser.had_error {
+        return None;
+    }
+
+    let mut linter = Linter {
+        scopes: vec![HashMap::new()],
+        warnings: Vec::new(),
+    };
+    linter.block(&stmts);
+
+    let directives = directives(&comments);
+    let mut warnings: Vec<Warning> = linter
+        .warnings
+        .into_iter()
+        .filter(|warning| is_enabled(&directives, warning))
+        .collect();
+    warnings.sort_by_key(|warning| warning.line);
+    Some(warnings)
+}
+
+/// `(line, enable, name)` for each name in each `// lint:` comment, in order.
+fn directives(comments: &[Token]) -> Vec<(usize, bool, String)> {
+    let mut directives = Vec::new();
+    for comment in comments {
+        let Some(directive) = comment
+            .lexeme
+            .trim_start_matches('/')
+            .trim()
+            .strip_prefix("lint:")
+        else {
+            continue;
+        };
+        let mut words = directive
+            .trim()
+            .split(|c: char| c.is_whitespace() || c == ',');
+        let enable = match words.next() {
+            Some("enable") => true,
+            Some("disable") => false,
+            _ => continue,
+        };
+        for name in words.filter(|name| !name.is_empty()) {
+            directives.push((comment.line, enable, name.to_string()));
+        }
+    }
+    directives
+}
+
+fn is_enabled(directives: &[(usize, bool, String)], warning: &Warning) -> bool {
+    directives
+        .iter()
+        .rev()
+        .find(|(line, _, name)| *line <= warning.line && (name == warning.name || name == "all"))
+        .is_none_or(|(_, enable, _)| *enable)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Variable,
+    Parameter,
+    Function,
+    /// Catch variables and imports, which are never reported as unused.
+    Other,
+}
+
+struct Binding {
+    line: usize,
+    kind: Kind,
+    used: bool,
+    /// Set while the name is known to hold a function declared with `fun`.
+    arity: Option<Arity>,
+}
+
+struct Linter {
+    /// Innermost last; the first scope holds the globals.
+    scopes: Vec<HashMap<String, Binding>>,
+    warnings: Vec<Warning>,
+}
+
+impl Linter {
+    fn warn(&mut self, line: usize, name: &'static str, message: String) {
+        self.warnings.push(Warning {
+            line,
+            name,
+            message,
+        });
+    }
+
+    fn declare(&mut self, name: &Token, kind: Kind, arity: Option<Arity>) {
+        if self.scopes.len() > 1 {
+            let outer = self.scopes[..self.scopes.len() - 1]
+                .iter()
+                .rev()
+                .find_map(|scope| scope.get(&name.lexeme));
+            if let Some(outer) = outer {
+                let message = format!(
+                    "'{}' shadows the declaration on line {}.",
+                    name.lexeme, outer.line
+                );
+                self.warn(name.line, "shadow", message);
+            }
+        }
+        let binding = Binding {
+            line: name.line,
+            kind,
+            used: false,
+            arity,
+        };
+        self.scopes
+            .last_mut()
+            .unwrap()
+            .insert(name.lexeme.clone(), binding);
+    }
+
+    fn lookup(&mut self, name: &str) -> Option<&mut Binding> {
+        self.scopes
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.get_mut(name))
+    }
+
+    fn begin_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    /// Ends a local scope, reporting the bindings in it that were never read.
+    fn end_scope(&mut self) {
+        let scope = self.scopes.pop().unwrap();
+        let mut unused: Vec<_> = scope
+            .into_iter()
+            .filter(|(name, binding)| !binding.used && !name.starts_with('_'))
+            .collect();
+        unused.sort_by_key(|(_, binding)| binding.line);
+        for (name, binding) in unused {
+            let what = match binding.kind {
+                Kind::Variable => "Local variable",
+                Kind::Parameter => "Parameter",
+                Kind::Function => "Local function",
+                Kind::Other => continue,
+            };
+            self.warn(
+                binding.line,
+                "unused",
+                format!("{} '{}' is never used.", what, name),
+            );
+        }
+    }
+
+    /// Checks a list of statements, reporting the first one after a
+    /// `return` or `throw`.
+    fn block(&mut self, stmts: &[Stmt]) {
+        let mut exit: Option<&Token> = None;
+        for stmt in stmts {
+            if let Some(keyword) = exit.take() {
+                let message = format!("Code after '{}' is never reached.", keyword.lexeme);
+                self.warn(stmt.line(), "unreachable", message);
+            }
+            self.stmt(stmt);
+            if let Stmt::Return { keyword, .. } | Stmt::Throw { keyword, .. } = stmt {
+                exit = Some(keyword);
+            }
+        }
+    }
+
+    fn function(&mut self, params: &[Param], body: &[Stmt]) {
+        self.begin_scope();
+        for param in params {
+            if let Some(default) = &param.default {
+                self.expr(default);
+            }
+            self.declare(&param.name, Kind::Parameter, None);
+        }
+        self.block(body);
+        self.end_scope();
+    }
+
+    fn condition(&mut self, condition: &Expr) {
+        let mut inner = condition;
+        while let Expr::Grouping { expression } = inner {
+            inner = expression;
+        }
+        let assignment = match inner {
+            Expr::Assign { name, .. } => Some((name.line, Some(name))),
+            Expr::CompoundAssign { target, op, .. } => match target.as_ref() {
+                Expr::Variable(name) => Some((op.line, Some(name))),
+                _ => Some((op.line, None)),
+            },
+            Expr::SetIndex { bracket, .. } => Some((bracket.line, None)),
+            _ => None,
+        };
+        if let Some((line, name)) = assignment {
+            let message = match name {
+                Some(name) => format!("Assignment to '{}' used as a condition.", name.lexeme),
+                None => "Assignment used as a condition.".to_string(),
+            };
+            self.warn(line, "assign-in-condition", message);
+        }
+        self.expr(condition);
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Block { stmts, .. } => {
+                self.begin_scope();
+                self.block(stmts);
+                self.end_scope();
+            }
+            Stmt::Expr { expr, .. } | Stmt::Print { value: expr, .. } => self.expr(expr),
+            Stmt::Function { name, params, body } => {
+                self.declare(name, Kind::Function, Some(Arity::of_params(params)));
+                self.function(params, body);
+            }
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                self.condition(condition);
+                self.stmt(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.stmt(else_branch);
+                }
+            }
+            Stmt::Import { alias, .. } => {
+                if let Some(alias) = alias {
+                    self.declare(alias, Kind::Other, None);
+                }
+            }
+            Stmt::Return { value, .. } | Stmt::Throw { value, .. } => self.expr(value),
+            Stmt::Try {
+                body,
+                catch,
+                finally,
+                ..
+            } => {
+                self.begin_scope();
+                self.block(body);
+                self.end_scope();
+                if let Some((name, body)) = catch {
+                    self.begin_scope();
+                    self.declare(name, Kind::Other, None);
+                    self.block(body);
+                    self.end_scope();
+                }
+                if let Some(body) = finally {
+                    self.begin_scope();
+                    self.block(body);
+                    self.end_scope();
+                }
+            }
+            Stmt::Var { name, initializer } => {
+                self.expr(initializer);
+                self.declare(name, Kind::Variable, None);
+            }
+            Stmt::While {
+                condition, body, ..
+            } => {
+                self.condition(condition);
+                self.stmt(body);
+            }
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Variable(name) => {
+                if let Some(binding) = self.lookup(&name.lexeme) {
+                    binding.used = true;
+                }
+            }
+            Expr::Assign { name, value } => {
+                self.expr(value);
+                // The name may now hold anything, so its arity is unknown.
+                if let Some(binding) = self.lookup(&name.lexeme) {
+                    binding.arity = None;
+                }
+            }
+            Expr::Binary { left, op, right } => {
+                if is_comparison(&op.type_)
+                    && is_pure(left)
+                    && left.stringify() == right.stringify()
+                {
+                    self.warn(
+                        op.line,
+                        "self-compare",
+                        format!("'{}' is compared with itself.", left.stringify()),
+                    );
+                }
+                self.expr(left);
+                self.expr(right);
+            }
+            Expr::Logical { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            Expr::CompoundAssign { target, value, .. } => {
+                self.expr(target);
+                self.expr(value);
+            }
+            Expr::Increment { target, .. } => self.expr(target),
+            Expr::Call {
+                callee,
+                paren,
+                arguments,
+            } => {
+                if let Expr::Variable(name) = callee.as_ref() {
+                    let arity = self.lookup(&name.lexeme).and_then(|binding| binding.arity);
+                    if let Some(arity) = arity.filter(|arity| !arity.accepts(arguments.len())) {
+                        self.warn(
+                            paren.line,
+                            "arity",
+                            format!(
+                                "'{}' expects {} arguments but is passed {}.",
+                                name.lexeme,
+                                arity,
+                                arguments.len()
+                            ),
+                        );
+                    }
+                }
+                self.expr(callee);
+                for argument in arguments {
+                    self.expr(argument);
+                }
+            }
+            Expr::Get { object, .. } => self.expr(object),
+            Expr::Grouping { expression } => self.expr(expression),
+            Expr::Index { object, index, .. } => {
+                self.expr(object);
+                self.expr(index);
+            }
+            Expr::SetIndex {
+                object,
+                index,
+                value,
+                ..
+            } => {
+                self.expr(object);
+                self.expr(index);
+                self.expr(value);
+            }
+            Expr::Lambda(function) => {
+                if let Stmt::Function { params, body, .. } = function.as_ref() {
+                    self.function(params, body);
+                }
+            }
+            Expr::List(items) => {
+                for item in items {
+                    self.expr(item);
+                }
+            }
+            Expr::Unary { right, .. } => self.expr(right),
+            Expr::Literal(_) => (),
+        }
+    }
+}
+
+fn is_comparison(op: &TokenType) -> bool {
+    matches!(
+        op,
+        TokenType::EqualEqual
+            | TokenType::BangEqual
+            | TokenType::Less
+            | TokenType::LessEqual
+            | TokenType::Greater
+            | TokenType::GreaterEqual
+    )
+}
+
+/// Whether evaluating `expr` twice must give the same value: variables and
+/// the fields and constant indices of them.
+fn is_pure(expr: &Expr) -> bool {
+    match expr {
+        Expr::Variable(_) => true,
+        Expr::Get { object, .. } => is_pure(object),
+        Expr::Index { object, index, .. } => {
+            is_pure(object) && matches!(index.as_ref(), Expr::Literal(_))
+        }
+        Expr::Grouping { expression } => is_pure(expression),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn warnings(source: &str) -> Vec<(usize, &'static str)> {
+        lint(source)
+            .unwrap()
+            .into_iter()
+            .map(|warning| (warning.line, warning.name))
+            .collect()
+    }
+
+    #[test]
+    fn warnings_found() {
+        let source = "var a = 1;
+fun f(x, unused, _ignored) {
+  var local = 2;
+  var a = x;
+  return a;
+  print 1;
+}
+if (a = 2) print a;
+while (a == a) a = a + 1;
+f(1);
+f(1, 2, 3, 4);
+{
+  fun g() {}
+}
+try {} catch (e) {}
+if (a += 1) print a;
+var xs = [1];
+while ((xs[0] = 0)) print xs;
+";
+        assert_eq!(
+            warnings(source),
+            [
+                (2, "unused"),
+                (3, "unused"),
+                (4, "shadow"),
+                (6, "unreachable"),
+                (8, "assign-in-condition"),
+                (9, "self-compare"),
+                (10, "arity"),
+                (11, "arity"),
+                (13, "unused"),
+                (16, "assign-in-condition"),
+                (18, "assign-in-condition"),
+            ]
+        );
+        assert_eq!(
+            lint("var y = 0;\nif (y -= 1) y;").unwrap()[0].message,
+            "Assignment to 'y' used as a condition."
+        );
+        assert_eq!(
+            lint("fun f(a) { return a; }\nf();").unwrap()[0].message,
+            "'f' expects 1 arguments but is passed 0."
+        );
+    }
+
+    #[test]
+    fn comment_directives() {
+        let source = "fun f(x) {
+  // lint: disable unused, shadow
+  var y;
+  var f;
+  // lint: enable shadow
+  var g = fun (f) {};
+  return g(x);
+}
+print f(1, 2); // lint: disable all
+";
+        assert_eq!(warnings(source), [(6, "shadow")]);
+    }
+
+    #[test]
+    fn clean() {
+        let source = "fun fib(n, step = 1) {
+  if (n < 2) return n;
+  return fib(n - step) + fib(n - 2);
+}
+var f = fib;
+f = (a, b) => a + b;
+print f(1, 2);
+for (var i = 0; i < 3; i = i + 1) print i;
+for (var i = 0; i < 3; i = i + 1) print i;
+";
+        assert_eq!(warnings(source), []);
+    }
+}
@@ -0,0 +1,912 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{self, BufRead, Read, Write};
+use std::rc::Rc;
+
+use serde_json::{json, Value};
+
+use crate::expr::Expr;
+use crate::interpreter::Interpreter;
+use crate::output::{with_diagnostics, Buffer, Output};
+use crate::parser::Parser;
+use crate::scanner::{Scanner, KEYWORDS};
+use crate::stmt::{Param, Stmt};
+use crate::token::{Token, TokenType};
+use crate::types::{LitVal, LoxCallable};
+
+/// Serves the Language Server Protocol over `input` and `output` until the
+/// client sends `exit` or closes `input`.
+pub fn run_lsp(mut input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    let mut server = LanguageServer::new();
+    while let Some(message) = read_message(&mut input)? {
+        let message = match message {
+            Ok(message) => message,
+            Err(error) => {
+                // A message that can't be read has no id to answer to.
+                let reply = json!({
+                    "jsonrpc": "2.0",
+                    "id": null,
+                    "error": { "code": -32700, "message": format!("Parse error: {}", error) },
+                });
+                write_message(&mut output, &reply)?;
+                continue;
+            }
+        };
+        for reply in server.handle(&message) {
+            write_message(&mut output, &reply)?;
+        }
+        if message["method"] == "exit" {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Reads one `Content-Length`-framed JSON-RPC message, or `None` at end of
+/// input. A message that's malformed comes back as an error describing why,
+/// and reading can carry on after it.
+fn read_message(input: &mut impl BufRead) -> io::Result<Option<Result<Value, String>>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        match input.read_line(&mut header) {
+            Ok(0) => return Ok(None),
+            Ok(_) => (),
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                return Ok(Some(Err("header is not valid UTF-8".to_string())))
+            }
+            Err(e) => return Err(e),
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = Some(value.trim().parse::<u64>());
+            }
+        }
+    }
+    let length = match length {
+        Some(Ok(length)) => length,
+        Some(Err(_)) => return Ok(Some(Err("invalid Content-Length header".to_string()))),
+        None => return Ok(Some(Err("missing Content-Length header".to_string()))),
+    };
+    let mut body = Vec::new();
+    input.take(length).read_to_end(&mut body)?;
+    if (body.len() as u64) < length {
+        return Ok(None);
+    }
+    Ok(Some(
+        serde_json::from_slice(&body).map_err(|e| e.to_string()),
+    ))
+}
+
+fn write_message(output: &mut impl Write, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+/// The state of an LSP session: the open documents, by URI.
+pub struct LanguageServer {
+    documents: HashMap<String, String>,
+    /// The native functions and constants, with their hover text.
+    natives: Vec<(String, String)>,
+}
+
+impl Default for LanguageServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LanguageServer {
+    pub fn new() -> Self {
+        LanguageServer {
+            documents: HashMap::new(),
+            natives: natives(),
+        }
+    }
+
+    /// Handles one message from the client, returning the responses and
+    /// notifications to send back.
+    pub fn handle(&mut self, message: &Value) -> Vec<Value> {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+        let uri = params["textDocument"]["uri"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+
+        let result = match method {
+            "initialize" => json!({
+                "capabilities": {
+                    "textDocumentSync": 1,
+                    "definitionProvider": true,
+                    "referencesProvider": true,
+                    "hoverProvider": true,
+                    "documentSymbolProvider": true,
+                    "completionProvider": {},
+                },
+                "serverInfo": { "name": "rustlox" },
+            }),
+            "shutdown" => Value::Null,
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                self.documents.insert(uri.clone(), text.to_string());
+                return vec![self.publish_diagnostics(&uri)];
+            }
+            "textDocument/didChange" => {
+                // Only full-document sync is advertised, so the last change is the whole text.
+                if let Some(text) = params["contentChanges"]
+                    .as_array()
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str())
+                {
+                    self.documents.insert(uri.clone(), text.to_string());
+                }
+                return vec![self.publish_diagnostics(&uri)];
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                return vec![notification(
+                    "textDocument/publishDiagnostics",
+                    json!({ "uri": uri, "diagnostics": [] }),
+                )];
+            }
+            "textDocument/definition"
+            | "textDocument/references"
+            | "textDocument/hover"
+            | "textDocument/documentSymbol"
+            | "textDocument/completion" => {
+                let text = self.documents.get(&uri).cloned().unwrap_or_default();
+                let document = Document::new(&text, &self.natives);
+                let position = &params["position"];
+                let position = (
+                    position["line"].as_u64().unwrap_or_default() as usize,
+                    position["character"].as_u64().unwrap_or_default() as usize,
+                );
+                match method {
+                    "textDocument/definition" => document.definition(&uri, position),
+                    "textDocument/references" => {
+                        let include_declaration = params["context"]["includeDeclaration"]
+                            .as_bool()
+                            .unwrap_or(true);
+                        document.references(&uri, position, include_declaration)
+                    }
+                    "textDocument/hover" => document.hover(position),
+                    "textDocument/documentSymbol" => document.symbols(),
+                    _ => document.completion(position),
+                }
+            }
+            // Other notifications, such as `initialized` and `exit`, need no reply.
+            _ if message.get("id").is_none() => return Vec::new(),
+            _ => {
+                return vec![json!({
+                    "jsonrpc": "2.0",
+                    "id": message["id"],
+                    "error": {
+                        "code": -32601,
+                        "message": format!("Method not found: {}", method),
+                    },
+                })]
+            }
+        };
+        vec![json!({ "jsonrpc": "2.0", "id": message["id"], "result": result })]
+    }
+
+    fn publish_diagnostics(&self, uri: &str) -> Value {
+        let text = self.documents.get(uri).cloned().unwrap_or_default();
+        let document = Document::new(&text, &self.natives);
+        notification(
+            "textDocument/publishDiagnostics",
+            json!({ "uri": uri, "diagnostics": document.diagnostics }),
+        )
+    }
+}
+
+fn notification(method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "method": method, "params": params })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Variable,
+    Function,
+    Parameter,
+}
+
+/// A declared name.
+struct Symbol {
+    token: Token,
+    kind: Kind,
+    /// What hover shows, such as `fun f(a, b)`.
+    detail: String,
+}
+
+/// Everything the server knows about one version of a document.
+struct Document<'a> {
+    lines: Vec<&'a str>,
+    symbols: Vec<Symbol>,
+    /// Every use of a name, with the symbol it refers to if it's declared in
+    /// the document.
+    references: Vec<(Token, Option<usize>)>,
+    /// The `{`..`}` spans of the document, as (line, column) pairs.
+    blocks: Vec<((usize, usize), (usize, usize))>,
+    diagnostics: Vec<Value>,
+    natives: &'a [(String, String)],
+}
+
+impl<'a> Document<'a> {
+    fn new(text: &'a str, natives: &'a [(String, String)]) -> Self {
+        let mut document = Document {
+            lines: text.split('\n').collect(),
+            symbols: Vec::new(),
+            references: Vec::new(),
+            blocks: Vec::new(),
+            diagnostics: Vec::new(),
+            natives,
+        };
+
+        // Scan errors are only reported as text, so capture and read them back.
+        let errors = Buffer::default();
+        let sink: Output = Rc::new(RefCell::new(errors.clone()));
+        let tokens = with_diagnostics(&sink, || {
+            Scanner::new(text.to_string()).scan_tokens().clone()
+        });
+        for error in errors.contents().lines() {
+            if let Some((line, message)) = scan_error(error) {
+                let range = document.line_range(line);
+                document.error(range, message);
+            }
+        }
+        // Parse errors keep the token they were found at.
+        let mut parser = Parser::new(tokens.clone());
+        let silent: Output = Rc::new(RefCell::new(io::sink()));
+        let stmts = with_diagnostics(&silent, || parser.parse().unwrap_or_default());
+        for (token, message) in &parser.errors {
+            let range = document.range(token);
+            document.error(range, message.clone());
+        }
+
+        document.find_blocks(&tokens);
+        let mut resolver = Resolver {
+            document: &mut document,
+            scopes: vec![HashMap::new()],
+            unresolved: Vec::new(),
+            merges_imports: false,
+        };
+        resolver.block(&stmts);
+        resolver.finish();
+        document
+    }
+
+    fn error(&mut self, range: Value, message: String) {
+        self.diagnostics.push(json!({
+            "range": range,
+            "severity": 1,
+            "source": "rustlox",
+            "message": message,
+        }));
+    }
+
+    /// Pairs up braces; an unclosed one runs to the end of the document.
+    fn find_blocks(&mut self, tokens: &[Token]) {
+        let mut open = Vec::new();
+        for token in tokens {
+            match token.type_ {
+                TokenType::LeftBrace => open.push((token.line - 1, token.column)),
+                TokenType::RightBrace => {
+                    if let Some(start) = open.pop() {
+                        self.blocks.push((start, (token.line - 1, token.column)));
+                    }
+                }
+                _ => (),
+            }
+        }
+        let end = (self.lines.len(), 0);
+        self.blocks
+            .extend(open.into_iter().map(|start| (start, end)));
+    }
+
+    /// The innermost block containing `position`, as an index into `blocks`.
+    fn block_at(&self, position: (usize, usize)) -> Option<usize> {
+        (0..self.blocks.len())
+            .filter(|&i| self.blocks[i].0 < position && position <= self.blocks[i].1)
+            .max_by_key(|&i| self.blocks[i].0)
+    }
+
+    /// The block a symbol is visible in: the one it's declared in, or for a
+    /// parameter, the function body after it.
+    fn symbol_block(&self, symbol: &Symbol) -> Option<usize> {
+        let position = token_position(&symbol.token);
+        if symbol.kind == Kind::Parameter {
+            (0..self.blocks.len())
+                .filter(|&i| self.blocks[i].0 > position)
+                .min_by_key(|&i| self.blocks[i].0)
+        } else {
+            self.block_at(position)
+        }
+    }
+
+    /// Converts a character column to the UTF-16 offset LSP positions use.
+    fn utf16(&self, line: usize, column: usize) -> usize {
+        self.lines.get(line).map_or(column, |text| {
+            text.chars().take(column).map(char::len_utf16).sum()
+        })
+    }
+
+    /// Converts an LSP position to a (line, character column) pair.
+    fn position(&self, (line, character): (usize, usize)) -> (usize, usize) {
+        let mut units = 0;
+        let text = self.lines.get(line).copied().unwrap_or_default();
+        for (column, c) in text.chars().enumerate() {
+            if units >= character {
+                return (line, column);
+            }
+            units += c.len_utf16();
+        }
+        (line, text.chars().count())
+    }
+
+    fn range(&self, token: &Token) -> Value {
+        let (line, column) = token_position(token);
+        let start = self.utf16(line, column);
+        let end = start + token.lexeme.encode_utf16().count();
+        json!({
+            "start": { "line": line, "character": start },
+            "end": { "line": line, "character": end },
+        })
+    }
+
+    fn line_range(&self, line: usize) -> Value {
+        let line = line.saturating_sub(1);
+        let end = self
+            .lines
+            .get(line)
+            .map_or(0, |text| text.encode_utf16().count());
+        json!({
+            "start": { "line": line, "character": 0 },
+            "end": { "line": line, "character": end },
+        })
+    }
+
+    fn native(&self, name: &str) -> Option<String> {
+        self.natives
+            .iter()
+            .find(|(native, _)| native == name)
+            .map(|(_, detail)| detail.clone())
+    }
+
+    fn location(&self, uri: &str, token: &Token) -> Value {
+        json!({ "uri": uri, "range": self.range(token) })
+    }
+
+    /// The symbol declared or referred to at `position`, if any.
+    fn symbol_at(&self, position: (usize, usize)) -> Option<usize> {
+        let position = self.position(position);
+        let touches = |token: &Token| {
+            let (line, column) = token_position(token);
+            line == position.0
+                && column <= position.1
+                && position.1 <= column + token.lexeme.chars().count()
+        };
+        self.symbols
+            .iter()
+            .position(|symbol| touches(&symbol.token))
+            .or_else(|| {
+                self.references
+                    .iter()
+                    .find(|(token, _)| touches(token))
+                    .and_then(|(_, symbol)| *symbol)
+            })
+    }
+
+    /// The name used at `position`, even if it isn't declared in the document.
+    fn name_at(&self, position: (usize, usize)) -> Option<&Token> {
+        let position = self.position(position);
+        self.references
+            .iter()
+            .map(|(token, _)| token)
+            .find(|token| {
+                let (line, column) = token_position(token);
+                line == position.0
+                    && column <= position.1
+                    && position.1 <= column + token.lexeme.chars().count()
+            })
+    }
+
+    fn definition(&self, uri: &str, position: (usize, usize)) -> Value {
+        match self.symbol_at(position) {
+            Some(symbol) => self.location(uri, &self.symbols[symbol].token),
+            None => Value::Null,
+        }
+    }
+
+    fn references(&self, uri: &str, position: (usize, usize), include_declaration: bool) -> Value {
+        let Some(symbol) = self.symbol_at(position) else {
+            return json!([]);
+        };
+        let mut locations = Vec::new();
+        if include_declaration {
+            locations.push(self.location(uri, &self.symbols[symbol].token));
+        }
+        for (token, target) in &self.references {
+            if *target == Some(symbol) {
+                locations.push(self.location(uri, token));
+            }
+        }
+        Value::Array(locations)
+    }
+
+    fn hover(&self, position: (usize, usize)) -> Value {
+        let detail = match self.symbol_at(position) {
+            Some(symbol) => self.symbols[symbol].detail.clone(),
+            None => match self
+                .name_at(position)
+                .and_then(|token| self.native(&token.lexeme))
+            {
+                Some(detail) => detail,
+                None => return Value::Null,
+            },
+        };
+        json!({ "contents": { "kind": "markdown", "value": format!("```lox\n{}\n```", detail) } })
+    }
+
+    fn symbols(&self) -> Value {
+        let symbols: Vec<Value> = self
+            .symbols
+            .iter()
+            .filter(|symbol| symbol.kind != Kind::Parameter)
+            .map(|symbol| {
+                json!({
+                    "name": symbol.token.lexeme,
+                    "detail": symbol.detail,
+                    "kind": if symbol.kind == Kind::Function { 12 } else { 13 },
+                    "range": self.range(&symbol.token),
+                    "selectionRange": self.range(&symbol.token),
+                })
+            })
+            .collect();
+        Value::Array(symbols)
+    }
+
+    /// Keywords, natives, globals, and the locals declared before `position`
+    /// in the blocks around it.
+    fn completion(&self, position: (usize, usize)) -> Value {
+        let position = self.position(position);
+        let mut items: Vec<(String, u8, String)> = KEYWORDS
+            .iter()
+            .map(|(keyword, _)| (keyword.to_string(), 14, String::new()))
+            .collect();
+        for (name, value) in self.natives {
+            items.push((name.clone(), 3, value.clone()));
+        }
+        for symbol in &self.symbols {
+            let visible = match self.symbol_block(symbol) {
+                None => true,
+                Some(block) => {
+                    let (start, end) = self.blocks[block];
+                    token_position(&symbol.token) < position && start < position && position <= end
+                }
+            };
+            if visible {
+                let kind = match symbol.kind {
+                    Kind::Function => 3,
+                    _ => 6,
+                };
+                items.push((symbol.token.lexeme.clone(), kind, symbol.detail.clone()));
+            }
+        }
+        // Inner declarations come later, so keep the last of each name.
+        let mut seen = HashMap::new();
+        for (i, item) in items.iter().enumerate() {
+            seen.insert(item.0.clone(), i);
+        }
+        let items: Vec<Value> = items
+            .into_iter()
+            .enumerate()
+            .filter(|(i, item)| seen[&item.0] == *i)
+            .map(|(_, (label, kind, detail))| json!({ "label": label, "kind": kind, "detail": detail }))
+            .collect();
+        Value::Array(items)
+    }
+}
+
+/// A token's zero-based line and character column.
+fn token_position(token: &Token) -> (usize, usize) {
+    (token.line.saturating_sub(1), token.column)
+}
+
+/// Splits `[line N] Error: message` into its line and message.
+fn scan_error(error: &str) -> Option<(usize, String)> {
+    let rest = error.strip_prefix("[line ")?;
+    let (line, rest) = rest.split_once(']')?;
+    let message = rest.trim_start().strip_prefix("Error")?;
+    let message = message
+        .split_once(": ")
+        .map_or(message, |(_, message)| message);
+    Some((line.parse().ok()?, message.to_string()))
+}
+
+/// The native functions and constants, with their hover text.
+fn natives() -> Vec<(String, String)> {
+    let mut natives: Vec<(String, String)> = Interpreter::new()
+        .globals
+        .borrow()
+        .values()
+        .iter()
+        .map(|(name, value)| (name.clone(), describe_native(name, value)))
+        .collect();
+    natives.sort();
+    natives
+}
+
+fn describe_native(name: &str, value: &LitVal) -> String {
+    match value {
+        LitVal::Native(native) => format!("native fun {} ({} arguments)", name, native.arity()),
+        _ => format!("const {} = {}", name, value),
+    }
+}
+
+fn describe_function(name: &str, params: &[Param]) -> String {
+    let params: Vec<String> = params
+        .iter()
+        .map(|param| match (&param.default, param.rest) {
+            (_, true) => format!("...{}", param.name.lexeme),
+            (Some(default), false) => format!("{} = {}", param.name.lexeme, default.stringify()),
+            (None, false) => param.name.lexeme.clone(),
+        })
+        .collect();
+    format!("fun {}({})", name, params.join(", "))
+}
+
+/// Walks the syntax tree, matching each use of a name to its declaration.
+struct Resolver<'d, 'a> {
+    document: &'d mut Document<'a>,
+    /// Innermost last; the first scope holds the globals.
+    scopes: Vec<HashMap<String, usize>>,
+    /// Uses not matched to a local, which may name a global declared later.
+    unresolved: Vec<usize>,
+    /// Whether an `import` without `as` brings in names we can't see.
+    merges_imports: bool,
+}
+
+impl Resolver<'_, '_> {
+    fn declare(&mut self, token: &Token, kind: Kind, detail: String) {
+        let symbol = self.document.symbols.len();
+        self.document.symbols.push(Symbol {
+            token: token.clone(),
+            kind,
+            detail,
+        });
+        self.scopes
+            .last_mut()
+            .unwrap()
+            .insert(token.lexeme.clone(), symbol);
+    }
+
+    fn reference(&mut self, token: &Token) {
+        let symbol = self
+            .scopes
+            .iter()
+            .skip(1)
+            .rev()
+            .find_map(|scope| scope.get(&token.lexeme).copied());
+        if symbol.is_none() {
+            self.unresolved.push(self.document.references.len());
+        }
+        self.document.references.push((token.clone(), symbol));
+    }
+
+    /// Matches the remaining uses against the globals, reporting the rest.
+    fn finish(&mut self) {
+        let natives = self.document.natives;
+        for &i in &self.unresolved {
+            let token = self.document.references[i].0.clone();
+            let symbol = self.scopes[0].get(&token.lexeme).copied();
+            self.document.references[i].1 = symbol;
+            if symbol.is_some()
+                || self.merges_imports
+                || natives.iter().any(|(name, _)| *name == token.lexeme)
+            {
+                continue;
+            }
+            let diagnostic = json!({
+                "range": self.document.range(&token),
+                "severity": 1,
+                "source": "rustlox",
+                "message": format!("Undefined variable '{}'.", token.lexeme),
+            });
+            self.document.diagnostics.push(diagnostic);
+        }
+    }
+
+    fn block(&mut self, stmts: &[Stmt]) {
+        for stmt in stmts {
+            self.stmt(stmt);
+        }
+    }
+
+    fn scoped_block(&mut self, stmts: &[Stmt]) {
+        self.scopes.push(HashMap::new());
+        self.block(stmts);
+        self.scopes.pop();
+    }
+
+    fn function(&mut self, params: &[Param], body: &[Stmt]) {
+        self.scopes.push(HashMap::new());
+        for param in params {
+            if let Some(default) = &param.default {
+                self.expr(default);
+            }
+            let detail = format!("(parameter) {}", param.name.lexeme);
+            self.declare(&param.name, Kind::Parameter, detail);
+        }
+        self.block(body);
+        self.scopes.pop();
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Block { stmts, .. } => self.scoped_block(stmts),
+            Stmt::Expr { expr, .. } | Stmt::Print { value: expr, .. } => self.expr(expr),
+            Stmt::Function { name, params, body } => {
+                let detail = describe_function(&name.lexeme, params);
+                self.declare(name, Kind::Function, detail);
+                self.function(params, body);
+            }
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                self.expr(condition);
+                self.stmt(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.stmt(else_branch);
+                }
+            }
+            Stmt::Import { path, alias, .. } => match alias {
+                Some(alias) => {
+                    let detail = format!("import {} as {}", path.lexeme, alias.lexeme);
+                    self.declare(alias, Kind::Variable, detail);
+                }
+                None => self.merges_imports = true,
+            },
+            Stmt::Return { value, .. } | Stmt::Throw { value, .. } => self.expr(value),
+            Stmt::Try {
+                body,
+                catch,
+                finally,
+                ..
+            } => {
+                self.scoped_block(body);
+                if let Some((name, body)) = catch {
+                    self.scopes.push(HashMap::new());
+                    self.declare(name, Kind::Variable, format!("catch ({})", name.lexeme));
+                    self.block(body);
+                    self.scopes.pop();
+                }
+                if let Some(body) = finally {
+                    self.scoped_block(body);
+                }
+            }
+            Stmt::Var { name, initializer } => {
+                self.expr(initializer);
+                self.declare(name, Kind::Variable, format!("var {}", name.lexeme));
+            }
+            Stmt::While {
+                condition, body, ..
+            } => {
+                self.expr(condition);
+                self.stmt(body);
+            }
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Variable(name) => self.reference(name),
+            Expr::Assign { name, value } => {
+                self.expr(value);
+                self.reference(name);
+            }
+            Expr::Binary { left, right, .. } | Expr::Logical { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            Expr::CompoundAssign { target, value, .. } => {
+                self.expr(target);
+                self.expr(value);
+            }
+            Expr::Increment { target, .. } => self.expr(target),
+            Expr::Call {
+                callee, arguments, ..
+            } => {
+                self.expr(callee);
+                for argument in arguments {
+                    self.expr(argument);
+                }
+            }
+            Expr::Get { object, .. } => self.expr(object),
+            Expr::Grouping { expression } => self.expr(expression),
+            Expr::Index { object, index, .. } => {
+                self.expr(object);
+                self.expr(index);
+            }
+            Expr::SetIndex {
+                object,
+                index,
+                value,
+                ..
+            } => {
+                self.expr(object);
+                self.expr(index);
+                self.expr(value);
+            }
+            Expr::Lambda(function) => {
+                if let Stmt::Function { params, body, .. } = function.as_ref() {
+                    self.function(params, body);
+                }
+            }
+            Expr::List(items) => {
+                for item in items {
+                    self.expr(item);
+                }
+            }
+            Expr::Unary { right, .. } => self.expr(right),
+            Expr::Literal(_) => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URI: &str = "file:///test.lox";
+    const SOURCE: &str = "var total = 0;
+fun add(a, b = 1) {
+  var sum = a + b;
+  return sum;
+}
+total = add(total, 2);
+print missing;
+";
+
+    fn request(id: u64, method: &str, params: Value) -> Value {
+        json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+    }
+
+    fn position(line: u64, character: u64) -> Value {
+        json!({ "textDocument": { "uri": URI }, "position": { "line": line, "character": character } })
+    }
+
+    fn open(server: &mut LanguageServer, text: &str) -> Vec<Value> {
+        server.handle(&json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/didOpen",
+            "params": { "textDocument": { "uri": URI, "languageId": "lox", "version": 1, "text": text } },
+        }))
+    }
+
+    fn range(line: u64, start: u64, end: u64) -> Value {
+        json!({ "start": { "line": line, "character": start }, "end": { "line": line, "character": end } })
+    }
+
+    #[test]
+    fn session() {
+        let mut server = LanguageServer::new();
+        let reply = server.handle(&request(1, "initialize", json!({})));
+        assert_eq!(reply[0]["result"]["capabilities"]["hoverProvider"], true);
+
+        let published = open(&mut server, SOURCE);
+        let diagnostics = &published[0]["params"]["diagnostics"];
+        assert_eq!(diagnostics.as_array().unwrap().len(), 1);
+        assert_eq!(diagnostics[0]["message"], "Undefined variable 'missing'.");
+        assert_eq!(diagnostics[0]["range"], range(6, 6, 13));
+
+        let reply = server.handle(&request(2, "textDocument/definition", position(5, 14)));
+        assert_eq!(reply[0]["result"]["range"], range(0, 4, 9));
+
+        let mut params = position(2, 12);
+        params["context"] = json!({ "includeDeclaration": true });
+        let reply = server.handle(&request(3, "textDocument/references", params));
+        let ranges: Vec<&Value> = reply[0]["result"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|location| &location["range"])
+            .collect();
+        assert_eq!(ranges, [&range(1, 8, 9), &range(2, 12, 13)]);
+
+        let reply = server.handle(&request(4, "textDocument/hover", position(5, 9)));
+        assert_eq!(
+            reply[0]["result"]["contents"]["value"],
+            "```lox\nfun add(a, b = 1)\n```"
+        );
+
+        let reply = server.handle(&request(5, "textDocument/documentSymbol", position(0, 0)));
+        let names: Vec<&Value> = reply[0]["result"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|symbol| &symbol["name"])
+            .collect();
+        assert_eq!(names, ["total", "add", "sum"]);
+
+        let mut labels = |line, character| -> Vec<String> {
+            let reply = server.handle(&request(
+                6,
+                "textDocument/completion",
+                position(line, character),
+            ));
+            reply[0]["result"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|item| item["label"].as_str().unwrap().to_string())
+                .collect()
+        };
+        let inside = labels(3, 2);
+        assert!(["a", "b", "sum", "add", "total", "while", "sqrt"]
+            .iter()
+            .all(|name| inside.contains(&name.to_string())));
+        let outside = labels(6, 0);
+        assert!(!outside.contains(&"sum".to_string()) && !outside.contains(&"a".to_string()));
+
+        let reply = server.handle(&request(7, "unknown/method", json!({})));
+        assert_eq!(reply[0]["error"]["code"], -32601);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let mut server = LanguageServer::new();
+        let published = open(&mut server, "var a = 1 @;\nprint a +;\n");
+        let diagnostics = &published[0]["params"]["diagnostics"];
+        assert_eq!(diagnostics[0]["message"], "Unexpected character.");
+        assert_eq!(diagnostics[0]["range"], range(0, 0, 12));
+        assert_eq!(diagnostics[1]["message"], "Expect expression.");
+        assert_eq!(diagnostics[1]["range"], range(1, 9, 10));
+    }
+
+    #[test]
+    fn framing() {
+        let body = request(1, "shutdown", Value::Null).to_string();
+        let exit = json!({ "jsonrpc": "2.0", "method": "exit" }).to_string();
+        let input = format!(
+            "Content-Length: 5\r\n\r\n{{oopsContent-Length: x\r\n\r\n\
+             Content-Length: {}\r\n\r\n{}Content-Length: {}\r\n\r\n{}",
+            body.len(),
+            body,
+            exit.len(),
+            exit
+        );
+        let mut output = Vec::new();
+        run_lsp(input.as_bytes(), &mut output).unwrap();
+        let replies: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .split("Content-Length: ")
+            .skip(1)
+            .map(|message| serde_json::from_str(message.split_once("\r\n\r\n").unwrap().1).unwrap())
+            .collect();
+        assert_eq!(replies.len(), 3);
+        assert_eq!(replies[0]["error"]["code"], -32700);
+        assert_eq!(
+            replies[1]["error"]["message"],
+            "Parse error: invalid Content-Length header"
+        );
+        assert_eq!(
+            replies[2],
+            json!({ "jsonrpc": "2.0", "id": 1, "result": null })
+        );
+
+        // Input that ends partway through a message ends the session quietly.
+        let mut output = Vec::new();
+        run_lsp("Content-Length: 10\r\n\r\n{}".as_bytes(), &mut output).unwrap();
+        assert!(output.is_empty());
+    }
+}
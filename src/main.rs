@@ -1,20 +1,141 @@
 use anyhow::Result;
 
-use rustlox::Lox;
+use std::path::Path;
+
+use rustlox::{format_source, lint, run_lsp, run_tests, FsAccess, Lox, WARNINGS};
 
 fn main() -> Result<()> {
-    let args: Vec<String> = std::env::args().collect();
+    let mut args: Vec<String> = std::env::args().collect();
+
+    // Scripts run from the command line may read the current directory and
+    // their own, and write to them only when given --allow-write before the
+    // script or subcommand.
+    let allow_write = args.get(1).is_some_and(|arg| arg == "--allow-write");
+    if allow_write {
+        args.remove(1);
+    }
+    let mut lox = Lox::new();
+    lox.set_fs_access(fs_access(None, allow_write)?);
 
-    #[allow(clippy::comparison_chain)]
-    if args.len() > 2 {
-        println!("Usage: {} [script]", args[0]);
+    match &args[1..] {
+        [] => lox.run_prompt().unwrap(),
+        [command, dir] if command == "test" => {
+            let report = run_tests(Path::new(dir))?;
+            for (path, mismatches) in &report.failures {
+                println!("FAIL {}", path.display());
+                for mismatch in mismatches {
+                    println!("    {}", mismatch);
+                }
+            }
+            println!(
+                "{} passed, {} failed.",
+                report.passed,
+                report.failures.len()
+            );
+            if !report.failures.is_empty() {
+                std::process::exit(1);
+            }
+        }
+        [command, rest @ ..] if command == "fmt" && !rest.is_empty() => {
+            let check = rest[0] == "--check";
+            let paths = if check { &rest[1..] } else { rest };
+            let mut failed = false;
+            for path in paths {
+                let source = std::fs::read_to_string(path)?;
+                match format_source(&source) {
+                    Some(formatted) if formatted == source => (),
+                    Some(_) if check => {
+                        println!("Would reformat {}", path);
+                        failed = true;
+                    }
+                    Some(formatted) => std::fs::write(path, formatted)?,
+                    None => {
+                        eprintln!("Could not format {}", path);
+                        failed = true;
+                    }
+                }
+            }
+            if failed {
+                std::process::exit(1);
+            }
+        }
+        [command, paths @ ..] if command == "lint" && !paths.is_empty() => {
+            let mut failed = false;
+            for path in paths {
+                let source = std::fs::read_to_string(path)?;
+                match lint(&source) {
+                    Some(warnings) => {
+                        for warning in &warnings {
+                            println!(
+                                "{}:{}: warning: {} [{}]",
+                                path, warning.line, warning.message, warning.name
+                            );
+                        }
+                        failed |= !warnings.is_empty();
+                    }
+                    None => {
+                        eprintln!("Could not lint {}", path);
+                        failed = true;
+                    }
+                }
+            }
+            if failed {
+                std::process::exit(1);
+            }
+        }
+        [command, script] if command == "debug" => {
+            lox.set_fs_access(fs_access(Some(script), allow_write)?);
+            lox.debug_file(script, std::io::BufReader::new(std::io::stdin()))?
+        }
+        [command] if command == "lsp" => run_lsp(std::io::stdin().lock(), std::io::stdout())?,
+        [flag, script] if flag == "--tokens" || flag == "--ast" => {
+            let source = std::fs::read_to_string(script)?;
+            let ok = if flag == "--tokens" {
+                lox.print_tokens(&source)
+            } else {
+                lox.print_ast(&source)
+            };
+            if !ok {
+                std::process::exit(65);
+            }
+        }
+        [script] => {
+            lox.set_fs_access(fs_access(Some(script), allow_write)?);
+            lox.run_file(script)?
+        }
+        _ => {
+            println!("Usage: {} [--allow-write] [script]", args[0]);
+            println!("       {} --tokens|--ast <script>", args[0]);
+            println!("       {} test <dir>", args[0]);
+            println!("       {} fmt [--check] <script>...", args[0]);
+            println!("       {} lint <script>...", args[0]);
+            println!("       {} [--allow-write] debug <script>", args[0]);
+            println!("       {} lsp", args[0]);
+            println!();
+            println!(
+                "Lint warnings, which `// lint: disable <name>` turns off: {}",
+                WARNINGS.join(", ")
+            );
 
-        std::process::exit(64);
-    } else if args.len() == 2 {
-        Lox::new().run_file(&args[1])?;
-    } else {
-        Lox::new().run_prompt().unwrap();
+            std::process::exit(64);
+        }
     }
 
     Ok(())
 }
+
+/// What a script run from the command line may touch: the current directory,
+/// and the script's own so it can import the files beside it.
+fn fs_access(script: Option<&str>, allow_write: bool) -> Result<FsAccess> {
+    let mut roots = vec![std::env::current_dir()?];
+    if let Some(dir) = script.and_then(|script| Path::new(script).parent()) {
+        // A bare file name's parent is empty, which is the current directory.
+        if !dir.as_os_str().is_empty() {
+            roots.push(dir.to_path_buf());
+        }
+    }
+    Ok(FsAccess {
+        roots,
+        read_only: !allow_write,
+    })
+}
@@ -0,0 +1,163 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{
+    environment::Environment,
+    interpreter::{is_truthy, Interpreter, RuntimeError},
+    scanner::Scanner,
+    token::Token,
+    types::{Arity, Int, LitVal, NativeFn, NativeFunction, Number},
+};
+
+mod fs;
+mod io;
+mod math;
+mod string;
+
+pub use fs::FsAccess;
+pub use io::Input;
+
+type Natives = &'static [(&'static str, Arity, NativeFn)];
+
+const CORE: Natives = &[
+    ("clock", Arity::exact(0), clock),
+    ("int", Arity::exact(1), int),
+    ("float", Arity::exact(1), float),
+    ("type", Arity::exact(1), type_),
+    ("str", Arity::exact(1), str),
+    ("num", Arity::exact(1), num),
+    ("bool", Arity::exact(1), bool),
+];
+
+/// Defines every native function and constant in `globals`.
+pub fn define_globals(globals: &mut Environment) {
+    for natives in [
+        CORE,
+        math::NATIVES,
+        string::NATIVES,
+        fs::NATIVES,
+        io::NATIVES,
+    ] {
+        for &(name, arity, function) in natives {
+            globals.define(
+                name.to_string(),
+                LitVal::Native(NativeFunction::new(name, arity, function)),
+            );
+        }
+    }
+    for &(name, value) in math::CONSTANTS {
+        globals.define_constant(name.to_string(), LitVal::Number(value));
+    }
+}
+
+fn number(paren: &Token, value: &LitVal) -> Result<Number, RuntimeError> {
+    value
+        .as_number()
+        .ok_or_else(|| RuntimeError::new(paren.clone(), "Argument must be a number."))
+}
+
+fn string<'a>(paren: &Token, value: &'a LitVal) -> Result<&'a str, RuntimeError> {
+    match value {
+        LitVal::String(s) => Ok(s),
+        _ => Err(RuntimeError::new(
+            paren.clone(),
+            "Argument must be a string.",
+        )),
+    }
+}
+
+fn integer(paren: &Token, value: &LitVal) -> Result<Int, RuntimeError> {
+    match value {
+        LitVal::Int(i) => Ok(*i),
+        _ => Err(RuntimeError::new(
+            paren.clone(),
+            "Argument must be an integer.",
+        )),
+    }
+}
+
+/// Converts a whole float to an int, failing if it's out of range or not finite.
+fn to_int(paren: &Token, n: Number) -> Result<Int, RuntimeError> {
+    // `Int::MAX as Number` rounds up to 2^63, which is itself out of range.
+    if n.is_finite() && n >= Int::MIN as Number && n < Int::MAX as Number {
+        Ok(n as Int)
+    } else {
+        Err(RuntimeError::new(
+            paren.clone(),
+            &format!("Cannot convert {} to an integer.", LitVal::Number(n)),
+        ))
+    }
+}
+
+fn clock(
+    _interpreter: &mut Interpreter,
+    _paren: &Token,
+    _arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    let start = SystemTime::now();
+    let since_the_epoch = start
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards");
+    Ok(LitVal::Number(since_the_epoch.as_secs_f64()))
+}
+
+/// Truncates a float toward zero.
+fn int(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    match arguments[0] {
+        LitVal::Int(i) => Ok(LitVal::Int(i)),
+        _ => Ok(LitVal::Int(to_int(
+            paren,
+            number(paren, &arguments[0])?.trunc(),
+        )?)),
+    }
+}
+
+fn float(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    Ok(LitVal::Number(number(paren, &arguments[0])?))
+}
+
+fn type_(
+    _interpreter: &mut Interpreter,
+    _paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    Ok(LitVal::String(arguments[0].type_name().to_string()))
+}
+
+fn str(
+    _interpreter: &mut Interpreter,
+    _paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    Ok(LitVal::String(arguments[0].to_string()))
+}
+
+/// Parses a number from a string with the syntax of number literals, returning
+/// `nil` if it isn't one.
+fn num(
+    _interpreter: &mut Interpreter,
+    _paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    let s = match &arguments[0] {
+        LitVal::Int(_) | LitVal::Number(_) => return Ok(arguments[0].clone()),
+        LitVal::String(s) => s.trim(),
+        _ => return Ok(LitVal::Nil),
+    };
+    Ok(Scanner::parse_number(s).unwrap_or(LitVal::Nil))
+}
+
+fn bool(
+    _interpreter: &mut Interpreter,
+    _paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    Ok(LitVal::Bool(is_truthy(&arguments[0])))
+}
@@ -0,0 +1,263 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    interpreter::{Interpreter, RuntimeError},
+    token::Token,
+    types::{Arity, LitVal},
+};
+
+use super::{string, Natives};
+
+pub const NATIVES: Natives = &[
+    ("read_file", Arity::exact(1), read_file),
+    ("write_file", Arity::exact(2), write_file),
+    ("append_file", Arity::exact(2), append_file),
+    ("read_lines", Arity::exact(1), read_lines),
+    ("file_exists", Arity::exact(1), file_exists),
+    ("list_dir", Arity::exact(1), list_dir),
+];
+
+/// Which parts of the filesystem scripts may use. The default allows nothing.
+#[derive(Debug, Clone, Default)]
+pub struct FsAccess {
+    /// Directories that scripts may access, along with everything below them.
+    pub roots: Vec<PathBuf>,
+    /// Rejects `write_file` and `append_file` when set.
+    pub read_only: bool,
+}
+
+impl FsAccess {
+    /// Resolves `path` and checks that it lies under one of the roots.
+    /// Symlinks and `..` are resolved first, so neither can escape a root.
+    pub(crate) fn check(
+        &self,
+        paren: &Token,
+        path: &str,
+        write: bool,
+    ) -> Result<PathBuf, RuntimeError> {
+        let denied = || {
+            RuntimeError::new(
+                paren.clone(),
+                &format!("Access to '{}' is not allowed.", path),
+            )
+        };
+        if write && self.read_only {
+            return Err(RuntimeError::new(
+                paren.clone(),
+                &format!("Can't write to '{}' in read-only mode.", path),
+            ));
+        }
+
+        // A file that doesn't exist yet is checked through its parent directory.
+        // A dangling symlink can't be resolved, and writing through it could
+        // create its target anywhere, so it's refused.
+        let path_buf = PathBuf::from(path);
+        let resolved = match path_buf.canonicalize() {
+            Ok(resolved) => resolved,
+            Err(_) if fs::symlink_metadata(&path_buf).is_ok() => return Err(denied()),
+            Err(_) => {
+                let parent = match path_buf.parent() {
+                    Some(parent) if !parent.as_os_str().is_empty() => parent,
+                    _ => Path::new("."),
+                };
+                let name = path_buf.file_name().ok_or_else(denied)?;
+                parent.canonicalize().map_err(|_| denied())?.join(name)
+            }
+        };
+
+        let allowed = self.roots.iter().any(|root| {
+            root.canonicalize()
+                .is_ok_and(|root| resolved.starts_with(root))
+        });
+        if allowed {
+            Ok(resolved)
+        } else {
+            Err(denied())
+        }
+    }
+}
+
+fn io_error(paren: &Token, action: &str, path: &str, error: std::io::Error) -> RuntimeError {
+    RuntimeError::new(
+        paren.clone(),
+        &format!("Could not {} '{}': {}.", action, path, error),
+    )
+}
+
+fn read_file(
+    interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    let path = string(paren, &arguments[0])?;
+    let resolved = interpreter.fs_access.check(paren, path, false)?;
+    fs::read_to_string(resolved)
+        .map(LitVal::String)
+        .map_err(|e| io_error(paren, "read", path, e))
+}
+
+fn write_file(
+    interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    let path = string(paren, &arguments[0])?;
+    let contents = string(paren, &arguments[1])?;
+    let resolved = interpreter.fs_access.check(paren, path, true)?;
+    fs::write(resolved, contents)
+        .map(|_| LitVal::Nil)
+        .map_err(|e| io_error(paren, "write", path, e))
+}
+
+fn append_file(
+    interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    let path = string(paren, &arguments[0])?;
+    let contents = string(paren, &arguments[1])?;
+    let resolved = interpreter.fs_access.check(paren, path, true)?;
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(resolved)
+        .and_then(|mut file| file.write_all(contents.as_bytes()))
+        .map(|_| LitVal::Nil)
+        .map_err(|e| io_error(paren, "append to", path, e))
+}
+
+fn read_lines(
+    interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    let path = string(paren, &arguments[0])?;
+    let resolved = interpreter.fs_access.check(paren, path, false)?;
+    let contents = fs::read_to_string(resolved).map_err(|e| io_error(paren, "read", path, e))?;
+    Ok(LitVal::list(
+        contents
+            .lines()
+            .map(|line| LitVal::String(line.to_string()))
+            .collect(),
+    ))
+}
+
+fn file_exists(
+    interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    let path = string(paren, &arguments[0])?;
+    let resolved = interpreter.fs_access.check(paren, path, false)?;
+    Ok(LitVal::Bool(resolved.exists()))
+}
+
+/// The names of the entries in a directory, sorted.
+fn list_dir(
+    interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    let path = string(paren, &arguments[0])?;
+    let resolved = interpreter.fs_access.check(paren, path, false)?;
+    let mut names = fs::read_dir(resolved)
+        .and_then(|entries| {
+            entries
+                .map(|entry| entry.map(|e| e.file_name().to_string_lossy().into_owned()))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|e| io_error(paren, "list", path, e))?;
+    names.sort();
+    Ok(LitVal::list(
+        names.into_iter().map(LitVal::String).collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parser::Parser, scanner::Scanner, test_util::TempDir};
+
+    use super::*;
+
+    #[test]
+    fn sandbox() {
+        let dir = TempDir::new("fs");
+        let root = dir.join("root");
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(dir.join("secret.txt"), "secret").unwrap();
+
+        let run = |access: &FsAccess, program: &str| {
+            let program = program.replace("ROOT", root.to_str().unwrap());
+            let mut scanner = Scanner::new(format!(
+                "var r; try {{ r = {}; }} catch (e) {{ r = e.message; }}",
+                program
+            ));
+            let stmts = Parser::new(scanner.scan_tokens().clone()).parse().unwrap();
+            let mut interpreter = Interpreter::new();
+            interpreter.fs_access = access.clone();
+            interpreter.interpret(stmts);
+            let mut scanner = Scanner::new("r".to_string());
+            let r = Parser::new(scanner.scan_tokens().clone())
+                .expression()
+                .unwrap();
+            interpreter
+                .evaluate_in(&r, interpreter.globals.clone())
+                .unwrap()
+                .to_string()
+                .replace(dir.to_str().unwrap(), "DIR")
+        };
+
+        let access = FsAccess {
+            roots: vec![root.clone()],
+            read_only: false,
+        };
+        assert_eq!(run(&access, "write_file(\"ROOT/a.txt\", \"1\n\")"), "nil");
+        assert_eq!(run(&access, "append_file(\"ROOT/a.txt\", \"2\n\")"), "nil");
+        assert_eq!(run(&access, "read_lines(\"ROOT/a.txt\")"), "[1, 2]");
+        assert_eq!(run(&access, "read_file(\"ROOT/sub/../a.txt\")"), "1\n2\n");
+        assert_eq!(
+            run(
+                &access,
+                "[file_exists(\"ROOT/a.txt\"), file_exists(\"ROOT/b\")]"
+            ),
+            "[true, false]"
+        );
+        assert_eq!(run(&access, "list_dir(\"ROOT\")"), "[a.txt, sub]");
+        assert_eq!(
+            run(&access, "read_file(\"ROOT/../secret.txt\")"),
+            "Access to 'DIR/root/../secret.txt' is not allowed."
+        );
+        assert_eq!(
+            run(&access, "read_file(\"ROOT/missing.txt\")"),
+            "Could not read 'DIR/root/missing.txt': No such file or directory (os error 2)."
+        );
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(dir.join("escaped.txt"), root.join("link.txt")).unwrap();
+            assert_eq!(
+                run(&access, "write_file(\"ROOT/link.txt\", \"\")"),
+                "Access to 'DIR/root/link.txt' is not allowed."
+            );
+            assert!(!dir.join("escaped.txt").exists());
+            fs::remove_file(root.join("link.txt")).unwrap();
+        }
+
+        let read_only = FsAccess {
+            read_only: true,
+            ..access
+        };
+        assert_eq!(run(&read_only, "read_file(\"ROOT/a.txt\")"), "1\n2\n");
+        assert_eq!(
+            run(&read_only, "write_file(\"ROOT/a.txt\", \"\")"),
+            "Can't write to 'DIR/root/a.txt' in read-only mode."
+        );
+        assert_eq!(
+            run(&FsAccess::default(), "file_exists(\"ROOT/a.txt\")"),
+            "Access to 'DIR/root/a.txt' is not allowed."
+        );
+    }
+}
@@ -0,0 +1,93 @@
+use std::{
+    cell::RefCell,
+    io::{BufRead, Read},
+    rc::Rc,
+};
+
+use crate::{
+    interpreter::{Interpreter, RuntimeError},
+    token::Token,
+    types::{Arity, LitVal},
+};
+
+use super::{string, Natives};
+
+pub const NATIVES: Natives = &[
+    (
+        "input",
+        Arity {
+            min: 0,
+            max: Some(1),
+        },
+        input,
+    ),
+    ("read_line", Arity::exact(0), read_line),
+    ("read_all", Arity::exact(0), read_all),
+];
+
+/// A replacement for stdin, shared so it survives across `Lox::run` calls.
+pub type Input = Rc<RefCell<dyn BufRead>>;
+
+fn io_error(paren: &Token, error: std::io::Error) -> RuntimeError {
+    RuntimeError::new(paren.clone(), &format!("Could not read input: {}.", error))
+}
+
+/// Reads one line without its line ending, or `None` at end of input.
+fn next_line(interpreter: &mut Interpreter, paren: &Token) -> Result<Option<String>, RuntimeError> {
+    let mut line = String::new();
+    let read = match &interpreter.input {
+        Some(input) => input.borrow_mut().read_line(&mut line),
+        None => std::io::stdin().read_line(&mut line),
+    }
+    .map_err(|e| io_error(paren, e))?;
+    if read == 0 {
+        return Ok(None);
+    }
+    if line.ends_with('\n') {
+        line.pop();
+        if line.ends_with('\r') {
+            line.pop();
+        }
+    }
+    Ok(Some(line))
+}
+
+/// Prints the optional prompt, then reads a line.
+fn input(
+    interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    if let Some(prompt) = arguments.first() {
+        let mut output = interpreter.output.borrow_mut();
+        write!(output, "{}", string(paren, prompt)?)
+            .and_then(|_| output.flush())
+            .map_err(|e| {
+                RuntimeError::new(paren.clone(), &format!("Could not write output: {}.", e))
+            })?;
+    }
+    read_line(interpreter, paren, Vec::new())
+}
+
+fn read_line(
+    interpreter: &mut Interpreter,
+    paren: &Token,
+    _arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    Ok(next_line(interpreter, paren)?.map_or(LitVal::Nil, LitVal::String))
+}
+
+/// Reads everything up to the end of input, which is `""` if nothing is left.
+fn read_all(
+    interpreter: &mut Interpreter,
+    paren: &Token,
+    _arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    let mut contents = String::new();
+    match &interpreter.input {
+        Some(input) => input.borrow_mut().read_to_string(&mut contents),
+        None => std::io::stdin().read_to_string(&mut contents),
+    }
+    .map_err(|e| io_error(paren, e))?;
+    Ok(LitVal::String(contents))
+}
@@ -0,0 +1,210 @@
+use std::f64::consts;
+
+use crate::{
+    interpreter::{Interpreter, RuntimeError},
+    token::Token,
+    types::{Arity, LitVal, Number},
+};
+
+use super::{number, to_int, Natives};
+
+pub const NATIVES: Natives = &[
+    ("sqrt", Arity::exact(1), sqrt),
+    ("pow", Arity::exact(2), pow),
+    ("abs", Arity::exact(1), abs),
+    ("floor", Arity::exact(1), floor),
+    ("ceil", Arity::exact(1), ceil),
+    ("round", Arity::exact(1), round),
+    ("min", Arity { min: 1, max: None }, min),
+    ("max", Arity { min: 1, max: None }, max),
+    ("sin", Arity::exact(1), sin),
+    ("cos", Arity::exact(1), cos),
+    ("tan", Arity::exact(1), tan),
+    ("atan2", Arity::exact(2), atan2),
+    ("log", Arity::exact(1), log),
+    ("exp", Arity::exact(1), exp),
+];
+
+/// Defined as constants, so `PI = 3;` is a runtime error.
+pub const CONSTANTS: &[(&str, Number)] = &[
+    ("PI", consts::PI),
+    ("E", consts::E),
+    ("INF", Number::INFINITY),
+    ("NAN", Number::NAN),
+];
+
+/// Applies `f` to a single numeric argument, always giving a float.
+fn float_fn(
+    paren: &Token,
+    arguments: &[LitVal],
+    f: fn(Number) -> Number,
+) -> Result<LitVal, RuntimeError> {
+    Ok(LitVal::Number(f(number(paren, &arguments[0])?)))
+}
+
+fn sqrt(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    float_fn(paren, &arguments, Number::sqrt)
+}
+
+/// Like `**`: two ints give an int unless the exponent is negative.
+fn pow(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    if let (LitVal::Int(a), LitVal::Int(b)) = (&arguments[0], &arguments[1]) {
+        if *b >= 0 {
+            return u32::try_from(*b)
+                .ok()
+                .and_then(|b| a.checked_pow(b))
+                .map(LitVal::Int)
+                .ok_or_else(|| RuntimeError::new(paren.clone(), "Integer overflow."));
+        }
+    }
+    let a = number(paren, &arguments[0])?;
+    let b = number(paren, &arguments[1])?;
+    Ok(LitVal::Number(a.powf(b)))
+}
+
+fn abs(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    match arguments[0] {
+        LitVal::Int(i) => i
+            .checked_abs()
+            .map(LitVal::Int)
+            .ok_or_else(|| RuntimeError::new(paren.clone(), "Integer overflow.")),
+        _ => float_fn(paren, &arguments, Number::abs),
+    }
+}
+
+/// Rounds with `f`, returning an int. Ints are returned unchanged; NaN,
+/// infinities and floats outside the int range are runtime errors.
+fn round_fn(
+    paren: &Token,
+    arguments: &[LitVal],
+    f: fn(Number) -> Number,
+) -> Result<LitVal, RuntimeError> {
+    match arguments[0] {
+        LitVal::Int(i) => Ok(LitVal::Int(i)),
+        _ => Ok(LitVal::Int(to_int(
+            paren,
+            f(number(paren, &arguments[0])?),
+        )?)),
+    }
+}
+
+fn floor(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    round_fn(paren, &arguments, Number::floor)
+}
+
+fn ceil(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    round_fn(paren, &arguments, Number::ceil)
+}
+
+/// Rounds half away from zero.
+fn round(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    round_fn(paren, &arguments, Number::round)
+}
+
+/// Returns whichever argument `better` prefers, keeping its int or float type.
+fn extreme(
+    paren: &Token,
+    arguments: Vec<LitVal>,
+    better: fn(Number, Number) -> bool,
+) -> Result<LitVal, RuntimeError> {
+    let mut best = None;
+    for argument in arguments {
+        let n = number(paren, &argument)?;
+        match best {
+            Some((m, _)) if !better(n, m) => (),
+            _ => best = Some((n, argument)),
+        }
+    }
+    Ok(best.expect("arity requires an argument").1)
+}
+
+fn min(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    extreme(paren, arguments, |a, b| a < b)
+}
+
+fn max(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    extreme(paren, arguments, |a, b| a > b)
+}
+
+fn sin(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    float_fn(paren, &arguments, Number::sin)
+}
+
+fn cos(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    float_fn(paren, &arguments, Number::cos)
+}
+
+fn tan(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    float_fn(paren, &arguments, Number::tan)
+}
+
+fn atan2(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    let y = number(paren, &arguments[0])?;
+    let x = number(paren, &arguments[1])?;
+    Ok(LitVal::Number(y.atan2(x)))
+}
+
+/// The natural logarithm.
+fn log(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    float_fn(paren, &arguments, Number::ln)
+}
+
+fn exp(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    float_fn(paren, &arguments, Number::exp)
+}
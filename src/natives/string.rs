@@ -0,0 +1,266 @@
+use crate::{
+    interpreter::{Interpreter, RuntimeError},
+    token::Token,
+    types::{Arity, Int, LitVal},
+};
+
+use super::{integer, string, Natives};
+
+// Lengths and indices count Unicode scalar values (`char`s), not bytes.
+pub const NATIVES: Natives = &[
+    ("len", Arity::exact(1), len),
+    (
+        "substr",
+        Arity {
+            min: 2,
+            max: Some(3),
+        },
+        substr,
+    ),
+    ("index_of", Arity::exact(2), index_of),
+    ("split", Arity::exact(2), split),
+    ("join", Arity::exact(2), join),
+    ("trim", Arity::exact(1), trim),
+    ("upper", Arity::exact(1), upper),
+    ("lower", Arity::exact(1), lower),
+    ("replace", Arity::exact(3), replace),
+    ("starts_with", Arity::exact(2), starts_with),
+    ("ends_with", Arity::exact(2), ends_with),
+    ("repeat", Arity::exact(2), repeat),
+    ("char_at", Arity::exact(2), char_at),
+    ("ord", Arity::exact(1), ord),
+    ("chr", Arity::exact(1), chr),
+];
+
+/// Checks that `i` is a position in `0..=len`, or `0..len` if `inclusive` is false.
+fn position(paren: &Token, i: Int, len: usize, inclusive: bool) -> Result<usize, RuntimeError> {
+    match usize::try_from(i) {
+        Ok(i) if i < len || (inclusive && i == len) => Ok(i),
+        _ => Err(RuntimeError::new(
+            paren.clone(),
+            &format!("Index {} is out of range for length {}.", i, len),
+        )),
+    }
+}
+
+/// The length of a string or a list.
+fn len(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    match &arguments[0] {
+        LitVal::String(s) => Ok(LitVal::Int(s.chars().count() as Int)),
+        LitVal::List(items) => Ok(LitVal::Int(items.borrow().len() as Int)),
+        _ => Err(RuntimeError::new(
+            paren.clone(),
+            "Argument must be a string or a list.",
+        )),
+    }
+}
+
+/// `substr(s, start, length)`, where `length` defaults to the rest of the string.
+fn substr(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    let s = string(paren, &arguments[0])?;
+    let len = s.chars().count();
+    let start = position(paren, integer(paren, &arguments[1])?, len, true)?;
+    let length = match arguments.get(2) {
+        Some(length) => {
+            let length = integer(paren, length)?;
+            if length < 0 {
+                return Err(RuntimeError::new(
+                    paren.clone(),
+                    "Length can't be negative.",
+                ));
+            }
+            let end = (start as Int)
+                .checked_add(length)
+                .ok_or_else(|| RuntimeError::new(paren.clone(), "Substring end out of range."))?;
+            position(paren, end, len, true)?;
+            length as usize
+        }
+        None => len - start,
+    };
+    Ok(LitVal::String(s.chars().skip(start).take(length).collect()))
+}
+
+/// The index of the first occurrence of `sub` in `s`, or -1.
+fn index_of(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    let s = string(paren, &arguments[0])?;
+    let sub = string(paren, &arguments[1])?;
+    Ok(LitVal::Int(match s.find(sub) {
+        Some(byte) => s[..byte].chars().count() as Int,
+        None => -1,
+    }))
+}
+
+/// Splits on `separator`, or into single characters if it's empty.
+fn split(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    let s = string(paren, &arguments[0])?;
+    let separator = string(paren, &arguments[1])?;
+    let parts: Vec<LitVal> = if separator.is_empty() {
+        s.chars().map(|c| LitVal::String(c.to_string())).collect()
+    } else {
+        s.split(separator)
+            .map(|part| LitVal::String(part.to_string()))
+            .collect()
+    };
+    Ok(LitVal::list(parts))
+}
+
+/// Joins the elements of a list with `separator`, printing non-strings as
+/// `print` would.
+fn join(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    let LitVal::List(items) = &arguments[0] else {
+        return Err(RuntimeError::new(
+            paren.clone(),
+            "First argument must be a list.",
+        ));
+    };
+    let separator = string(paren, &arguments[1])?;
+    let parts: Vec<String> = items.borrow().iter().map(|item| item.to_string()).collect();
+    Ok(LitVal::String(parts.join(separator)))
+}
+
+fn trim(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    Ok(LitVal::String(
+        string(paren, &arguments[0])?.trim().to_string(),
+    ))
+}
+
+fn upper(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    Ok(LitVal::String(string(paren, &arguments[0])?.to_uppercase()))
+}
+
+fn lower(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    Ok(LitVal::String(string(paren, &arguments[0])?.to_lowercase()))
+}
+
+/// Replaces every occurrence of `from` with `to`.
+fn replace(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    let s = string(paren, &arguments[0])?;
+    let from = string(paren, &arguments[1])?;
+    let to = string(paren, &arguments[2])?;
+    if from.is_empty() {
+        return Err(RuntimeError::new(
+            paren.clone(),
+            "Can't replace an empty string.",
+        ));
+    }
+    Ok(LitVal::String(s.replace(from, to)))
+}
+
+fn starts_with(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    let s = string(paren, &arguments[0])?;
+    let prefix = string(paren, &arguments[1])?;
+    Ok(LitVal::Bool(s.starts_with(prefix)))
+}
+
+fn ends_with(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    let s = string(paren, &arguments[0])?;
+    let suffix = string(paren, &arguments[1])?;
+    Ok(LitVal::Bool(s.ends_with(suffix)))
+}
+
+fn repeat(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    let s = string(paren, &arguments[0])?;
+    let count = integer(paren, &arguments[1])?;
+    match usize::try_from(count) {
+        Ok(count) => Ok(LitVal::String(s.repeat(count))),
+        Err(_) => Err(RuntimeError::new(
+            paren.clone(),
+            "Repeat count can't be negative.",
+        )),
+    }
+}
+
+fn char_at(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    let s = string(paren, &arguments[0])?;
+    let i = position(
+        paren,
+        integer(paren, &arguments[1])?,
+        s.chars().count(),
+        false,
+    )?;
+    Ok(LitVal::String(s.chars().nth(i).unwrap().to_string()))
+}
+
+/// The code point of a one-character string.
+fn ord(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    let mut chars = string(paren, &arguments[0])?.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(LitVal::Int(c as Int)),
+        _ => Err(RuntimeError::new(
+            paren.clone(),
+            "Argument must be a single character.",
+        )),
+    }
+}
+
+/// The one-character string for a code point.
+fn chr(
+    _interpreter: &mut Interpreter,
+    paren: &Token,
+    arguments: Vec<LitVal>,
+) -> Result<LitVal, RuntimeError> {
+    let code = integer(paren, &arguments[0])?;
+    match u32::try_from(code).ok().and_then(char::from_u32) {
+        Some(c) => Ok(LitVal::String(c.to_string())),
+        None => Err(RuntimeError::new(
+            paren.clone(),
+            &format!("{} is not a valid code point.", code),
+        )),
+    }
+}
@@ -0,0 +1,59 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+/// A writer shared between `Lox` and the interpreters it creates.
+pub type Output = Rc<RefCell<dyn Write>>;
+
+pub fn stdout() -> Output {
+    Rc::new(RefCell::new(io::stdout()))
+}
+
+pub fn stderr() -> Output {
+    Rc::new(RefCell::new(io::stderr()))
+}
+
+thread_local! {
+    /// Where diagnostics go while a `Lox` is running; stderr otherwise.
+    static DIAGNOSTICS: RefCell<Option<Output>> = const { RefCell::new(None) };
+}
+
+/// Runs `f` with diagnostics redirected to `sink`.
+pub fn with_diagnostics<T>(sink: &Output, f: impl FnOnce() -> T) -> T {
+    let previous = DIAGNOSTICS.with(|d| d.replace(Some(Rc::clone(sink))));
+    let result = f();
+    DIAGNOSTICS.with(|d| d.replace(previous));
+    result
+}
+
+/// Writes one line of diagnostics. Failures are ignored, since there's
+/// nowhere left to report them.
+pub fn diagnostic(args: fmt::Arguments) {
+    let sink = DIAGNOSTICS.with(|d| d.borrow().clone());
+    let _ = match sink {
+        Some(sink) => writeln!(sink.borrow_mut(), "{}", args),
+        None => writeln!(io::stderr(), "{}", args),
+    };
+}
+
+/// An in-memory sink whose clones share one buffer, so a test can keep a
+/// clone and read back what was written.
+#[derive(Debug, Clone, Default)]
+pub struct Buffer(Rc<RefCell<Vec<u8>>>);
+
+impl Buffer {
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.0.borrow()).into_owned()
+    }
+}
+
+impl Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -1,8 +1,8 @@
-use core::error;
+use std::rc::Rc;
 
 use crate::{
     expr::Expr,
-    stmt::Stmt,
+    stmt::{Param, Stmt},
     token::{Token, TokenType},
     types::LitVal,
     Lox,
@@ -14,11 +14,19 @@ pub struct ParseError;
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
+    pub had_error: bool,
+    /// The errors reported so far, with the token each was found at.
+    pub errors: Vec<(Token, String)>,
 }
 
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
-        Parser { tokens, current: 0 }
+        Parser {
+            tokens,
+            current: 0,
+            had_error: false,
+            errors: Vec::new(),
+        }
     }
 
     pub fn parse(&mut self) -> Result<Vec<Stmt>, ParseError> {
@@ -29,6 +37,15 @@ impl Parser {
         Ok(statements)
     }
 
+    /// Parses the tokens as one expression with nothing after it.
+    pub fn parse_expression(&mut self) -> Result<Expr, ParseError> {
+        let expr = self.expression()?;
+        if !self.is_at_end() {
+            return Err(self.error(self.peek(), "Expect end of expression."));
+        }
+        Ok(expr)
+    }
+
     fn statement(&mut self) -> Result<Stmt, ParseError> {
         if self.match_(&[TokenType::For]) {
             return self.for_stmt();
@@ -36,22 +53,36 @@ impl Parser {
         if self.match_(&[TokenType::If]) {
             return self.if_stmt();
         }
+        if self.match_(&[TokenType::Import]) {
+            return self.import_stmt();
+        }
         if self.match_(&[TokenType::Print]) {
             return self.print_stmt();
         }
         if self.match_(&[TokenType::Return]) {
             return self.return_stmt();
         }
+        if self.match_(&[TokenType::Throw]) {
+            return self.throw_stmt();
+        }
+        if self.match_(&[TokenType::Try]) {
+            return self.try_stmt();
+        }
         if self.match_(&[TokenType::While]) {
             return self.while_stmt();
         }
         if self.match_(&[TokenType::LeftBrace]) {
-            return Ok(Stmt::Block(self.block()?));
+            let brace = self.previous();
+            return Ok(Stmt::Block {
+                brace,
+                stmts: self.block()?,
+            });
         }
         self.expr_stmt()
     }
 
     fn for_stmt(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.previous();
         self.consume(&TokenType::LeftParen, "Expect '(' after 'for'.")?;
 
         let initializer = if self.match_(&[TokenType::Semicolon]) {
@@ -74,28 +105,42 @@ impl Parser {
         } else {
             Expr::Literal(LitVal::Nil)
         };
-        self.consume(&TokenType::RightParen, "Expect ')' after for clauses.")?;
+        let paren = self.consume(&TokenType::RightParen, "Expect ')' after for clauses.")?;
 
         let mut body = self.statement()?;
         if increment != Expr::Literal(LitVal::Nil) {
-            body = Stmt::Block(vec![body, Stmt::Expr(increment)]);
+            body = Stmt::Block {
+                brace: keyword.clone(),
+                stmts: vec![
+                    body,
+                    Stmt::Expr {
+                        expr: increment,
+                        semicolon: paren,
+                    },
+                ],
+            };
         }
 
         if condition == Expr::Literal(LitVal::Nil) {
             condition = Expr::Literal(LitVal::Bool(true));
         }
         body = Stmt::While {
+            keyword: keyword.clone(),
             condition,
             body: Box::new(body),
         };
         if let Some(initlzlr) = initializer {
-            body = Stmt::Block(vec![initlzlr, body]);
+            body = Stmt::Block {
+                brace: keyword,
+                stmts: vec![initlzlr, body],
+            };
         };
 
         Ok(body)
     }
 
     fn if_stmt(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.previous();
         self.consume(&TokenType::LeftParen, "Expect '(' after 'if'.")?;
         let condition = self.expression()?;
         self.consume(&TokenType::RightParen, "Expect ')' after if condition.")?;
@@ -108,19 +153,36 @@ impl Parser {
         };
 
         Ok(Stmt::If {
+            keyword,
             condition,
             then_branch,
             else_branch,
         })
     }
 
-    fn print_stmt(&mut self) -> Result<Stmt, ParseError> {
-        let val = match self.expression() {
-            Ok(v) => Ok(Stmt::Print(v)),
-            Err(e) => return Err(e),
+    fn import_stmt(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.previous();
+        let path = self.consume(&TokenType::String, "Expect module path after 'import'.")?;
+        // `as` is only special here, so it stays usable as a variable name.
+        let alias = if self.check(&TokenType::Identifier) && self.peek().lexeme == "as" {
+            self.advance();
+            Some(self.consume(&TokenType::Identifier, "Expect module name after 'as'.")?)
+        } else {
+            None
         };
+        self.consume(&TokenType::Semicolon, "Expect ';' after import.")?;
+        Ok(Stmt::Import {
+            keyword,
+            path,
+            alias,
+        })
+    }
+
+    fn print_stmt(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.previous();
+        let value = self.expression()?;
         self.consume(&TokenType::Semicolon, "Expect ';' after value.")?;
-        val
+        Ok(Stmt::Print { keyword, value })
     }
 
     fn return_stmt(&mut self) -> Result<Stmt, ParseError> {
@@ -135,49 +197,161 @@ impl Parser {
         Ok(Stmt::Return { keyword, value })
     }
 
+    fn throw_stmt(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.previous();
+        let value = self.expression()?;
+        self.consume(&TokenType::Semicolon, "Expect ';' after thrown value.")?;
+        Ok(Stmt::Throw { keyword, value })
+    }
+
+    fn try_stmt(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.previous();
+        self.consume(&TokenType::LeftBrace, "Expect '{' after 'try'.")?;
+        let body = self.block()?;
+
+        let catch = if self.match_(&[TokenType::Catch]) {
+            self.consume(&TokenType::LeftParen, "Expect '(' after 'catch'.")?;
+            let name = self.consume(&TokenType::Identifier, "Expect error variable name.")?;
+            self.consume(&TokenType::RightParen, "Expect ')' after error variable.")?;
+            self.consume(&TokenType::LeftBrace, "Expect '{' before catch body.")?;
+            Some((name, self.block()?))
+        } else {
+            None
+        };
+        let finally = if self.match_(&[TokenType::Finally]) {
+            self.consume(&TokenType::LeftBrace, "Expect '{' after 'finally'.")?;
+            Some(self.block()?)
+        } else {
+            None
+        };
+
+        if catch.is_none() && finally.is_none() {
+            return Err(self.error(keyword, "Expect 'catch' or 'finally' after try block."));
+        }
+        Ok(Stmt::Try {
+            keyword,
+            body,
+            catch,
+            finally,
+        })
+    }
+
     fn while_stmt(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.previous();
         self.consume(&TokenType::LeftParen, "Expect '(' after 'while'.")?;
         let condition = self.expression()?;
         self.consume(&TokenType::RightParen, "Expect ')' after after condition.")?;
         let body = self.statement()?;
 
         Ok(Stmt::While {
+            keyword,
             condition,
             body: Box::new(body),
         })
     }
 
     fn expr_stmt(&mut self) -> Result<Stmt, ParseError> {
-        let expr = match self.expression() {
-            Ok(expr) => Ok(Stmt::Expr(expr)),
-            Err(e) => return Err(e),
-        };
-        self.consume(&TokenType::Semicolon, "Expect ';' after expression.")?;
-        expr
+        let expr = self.expression()?;
+        let semicolon = self.consume(&TokenType::Semicolon, "Expect ';' after expression.")?;
+        Ok(Stmt::Expr { expr, semicolon })
     }
 
     fn function(&mut self, kind: &str) -> Result<Stmt, ParseError> {
         use TokenType as tt;
         let name = self.consume(&tt::Identifier, &format!("Expect {} name.", kind))?;
         self.consume(&tt::LeftParen, &format!("Expect '(' after {} name.", kind))?;
-        let mut params = Vec::new();
+        let params = self.parameters()?;
+
+        self.consume(&tt::LeftBrace, &format!("Expect '{{' before {kind} body."))?;
+        let body = self.block()?;
+        Ok(Stmt::Function { name, params, body })
+    }
+
+    /// Parses a parameter list up to and including the closing ')'.
+    fn parameters(&mut self) -> Result<Vec<Param>, ParseError> {
+        use TokenType as tt;
+        let mut params: Vec<Param> = Vec::new();
         if !self.check(&tt::RightParen) {
             loop {
                 if params.len() >= 255 {
                     self.error(self.peek(), "Can't have more than 255 parameters.");
                 }
-                params.push(self.consume(&tt::Identifier, "Expect parameter name.")?);
-
+                let rest = self.match_(&[tt::DotDotDot]);
+                let name = self.consume(&tt::Identifier, "Expect parameter name.")?;
+                let default = if !rest && self.match_(&[tt::Equal]) {
+                    Some(self.expression()?)
+                } else {
+                    None
+                };
+                if !rest && default.is_none() && params.iter().any(|p| p.default.is_some()) {
+                    self.error(
+                        name.clone(),
+                        "Parameter without a default value can't follow one with a default.",
+                    );
+                }
+                params.push(Param {
+                    name,
+                    default,
+                    rest,
+                });
+
+                if rest && self.check(&tt::Comma) {
+                    return Err(self.error(self.peek(), "Rest parameter must be last."));
+                }
                 if !self.match_(&[tt::Comma]) {
                     break;
                 }
             }
         }
         self.consume(&tt::RightParen, "Expect ')' after parameters.")?;
+        Ok(params)
+    }
 
-        self.consume(&tt::LeftBrace, &format!("Expect '{{' before {kind} body."))?;
+    /// `fun (a, b) { ... }`, with the `fun` already consumed.
+    fn lambda(&mut self) -> Result<Expr, ParseError> {
+        let keyword = self.previous();
+        self.consume(&TokenType::LeftParen, "Expect '(' after 'fun'.")?;
+        let params = self.parameters()?;
+        self.consume(&TokenType::LeftBrace, "Expect '{' before lambda body.")?;
         let body = self.block()?;
-        Ok(Stmt::Function { name, params, body })
+        Ok(lambda(keyword, params, body))
+    }
+
+    /// `(a, b) => a + b` or `(a, b) => { ... }`, with the '(' already consumed.
+    fn arrow_lambda(&mut self) -> Result<Expr, ParseError> {
+        let params = self.parameters()?;
+        let keyword = self.consume(&TokenType::Arrow, "Expect '=>' after parameters.")?;
+        let body = if self.match_(&[TokenType::LeftBrace]) {
+            self.block()?
+        } else {
+            let value = self.expression()?;
+            vec![Stmt::Return {
+                keyword: keyword.clone(),
+                value,
+            }]
+        };
+        Ok(lambda(keyword, params, body))
+    }
+
+    /// Looks past a '(' for its matching ')' followed by `=>`, to tell arrow
+    /// lambdas from groupings.
+    fn is_arrow_lambda(&self) -> bool {
+        let mut depth = 0;
+        for (i, token) in self.tokens.iter().enumerate().skip(self.current) {
+            match token.type_ {
+                TokenType::LeftParen => depth += 1,
+                TokenType::RightParen if depth > 0 => depth -= 1,
+                TokenType::RightParen => {
+                    return self
+                        .tokens
+                        .get(i + 1)
+                        .is_some_and(|t| t.type_ == TokenType::Arrow)
+                }
+                TokenType::Eof => return false,
+                _ => (),
+            }
+        }
+        false
     }
 
     fn block(&mut self) -> Result<Vec<Stmt>, ParseError> {
@@ -196,13 +370,18 @@ impl Parser {
             Ok(n) => Ok(n),
             Err(_) => {
                 self.synchronize();
-                Ok(Stmt::Expr(Expr::Literal(LitVal::Nil)))
+                Ok(Stmt::Expr {
+                    expr: Expr::Literal(LitVal::Nil),
+                    semicolon: self.previous(),
+                })
             }
         }
     }
 
     fn declaration_helper(&mut self) -> Result<Stmt, ParseError> {
-        if self.match_(&[TokenType::Fun]) {
+        // A `fun` without a name starts a lambda expression statement instead.
+        if self.check(&TokenType::Fun) && self.check_next(&TokenType::Identifier) {
+            self.advance();
             return self.function("function");
         }
         if self.match_(&[TokenType::Var]) {
@@ -246,15 +425,51 @@ impl Parser {
                         value: Box::new(value),
                     });
                 }
+                Expr::Index {
+                    object,
+                    bracket,
+                    index,
+                } => {
+                    return Ok(Expr::SetIndex {
+                        object,
+                        bracket,
+                        index,
+                        value: Box::new(value),
+                    });
+                }
                 _ => {
                     return Err(self.error(equals, "Invalid assignment target."));
                 }
             }
         }
 
+        if self.match_(&[
+            TokenType::PlusEqual,
+            TokenType::MinusEqual,
+            TokenType::StarEqual,
+            TokenType::SlashEqual,
+        ]) {
+            let op = self.previous();
+            let value = self.assignment()?;
+            let target = self.assignment_target(expr, &op)?;
+            return Ok(Expr::CompoundAssign {
+                target,
+                op,
+                value: Box::new(value),
+            });
+        }
+
         Ok(expr)
     }
 
+    /// Checks that `expr` can be written to by `op`.
+    fn assignment_target(&mut self, expr: Expr, op: &Token) -> Result<Box<Expr>, ParseError> {
+        match expr {
+            Expr::Variable(_) | Expr::Index { .. } => Ok(Box::new(expr)),
+            _ => Err(self.error(op.clone(), "Invalid assignment target.")),
+        }
+    }
+
     fn or(&mut self) -> Result<Expr, ParseError> {
         let mut expr = self.and()?;
 
@@ -288,9 +503,59 @@ impl Parser {
     }
 
     fn equality(&mut self) -> Result<Expr, ParseError> {
-        let mut expr = self.comparison()?;
+        let mut expr = self.bit_or()?;
 
         while self.match_(&[TokenType::BangEqual, TokenType::EqualEqual]) {
+            let operator = self.previous();
+            let right = self.bit_or()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                op: operator,
+                right: Box::new(right),
+            };
+        }
+
+        Ok(expr)
+    }
+
+    // The bitwise operators keep C's relative order but bind tighter than
+    // equality, so `x & 1 == 0` means `(x & 1) == 0`.
+    fn bit_or(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.bit_xor()?;
+
+        while self.match_(&[TokenType::Pipe]) {
+            let operator = self.previous();
+            let right = self.bit_xor()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                op: operator,
+                right: Box::new(right),
+            };
+        }
+
+        Ok(expr)
+    }
+
+    fn bit_xor(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.bit_and()?;
+
+        while self.match_(&[TokenType::Caret]) {
+            let operator = self.previous();
+            let right = self.bit_and()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                op: operator,
+                right: Box::new(right),
+            };
+        }
+
+        Ok(expr)
+    }
+
+    fn bit_and(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.comparison()?;
+
+        while self.match_(&[TokenType::Ampersand]) {
             let operator = self.previous();
             let right = self.comparison()?;
             expr = Expr::Binary {
@@ -304,7 +569,7 @@ impl Parser {
     }
 
     fn comparison(&mut self) -> Result<Expr, ParseError> {
-        let mut expr = self.term();
+        let mut expr = self.shift();
 
         while self.match_(&[
             TokenType::Greater,
@@ -312,6 +577,22 @@ impl Parser {
             TokenType::Less,
             TokenType::LessEqual,
         ]) {
+            let operator = self.previous();
+            let right = self.shift();
+            expr = Ok(Expr::Binary {
+                left: Box::new(expr?),
+                op: operator,
+                right: Box::new(right?),
+            })
+        }
+
+        expr
+    }
+
+    fn shift(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.term();
+
+        while self.match_(&[TokenType::LessLess, TokenType::GreaterGreater]) {
             let operator = self.previous();
             let right = self.term();
             expr = Ok(Expr::Binary {
@@ -343,7 +624,12 @@ impl Parser {
     fn factor(&mut self) -> Result<Expr, ParseError> {
         let mut expr = self.unary();
 
-        while self.match_(&[TokenType::Slash, TokenType::Star]) {
+        while self.match_(&[
+            TokenType::Slash,
+            TokenType::Star,
+            TokenType::Percent,
+            TokenType::TildeSlash,
+        ]) {
             let operator = self.previous();
             let right = self.unary();
             expr = Ok(Expr::Binary {
@@ -357,7 +643,16 @@ impl Parser {
     }
 
     fn unary(&mut self) -> Result<Expr, ParseError> {
-        if self.match_(&[TokenType::Bang, TokenType::Minus]) {
+        if self.match_(&[TokenType::PlusPlus, TokenType::MinusMinus]) {
+            let op = self.previous();
+            let right = self.unary()?;
+            return Ok(Expr::Increment {
+                target: self.assignment_target(right, &op)?,
+                op,
+                prefix: true,
+            });
+        }
+        if self.match_(&[TokenType::Bang, TokenType::Minus, TokenType::Tilde]) {
             let operator = self.previous();
             let right = self.unary();
             return Ok(Expr::Unary {
@@ -366,7 +661,25 @@ impl Parser {
             });
         }
 
-        self.call()
+        self.power()
+    }
+
+    /// `**` binds tighter than unary minus and is right-associative, so
+    /// `-2 ** 2` is `-(2 ** 2)` and `2 ** -1` is allowed.
+    fn power(&mut self) -> Result<Expr, ParseError> {
+        let expr = self.call()?;
+
+        if self.match_(&[TokenType::StarStar]) {
+            let operator = self.previous();
+            let right = self.unary()?;
+            return Ok(Expr::Binary {
+                left: Box::new(expr),
+                op: operator,
+                right: Box::new(right),
+            });
+        }
+
+        Ok(expr)
     }
 
     fn finish_call(&mut self, callee: Expr) -> Result<Expr, ParseError> {
@@ -397,6 +710,29 @@ impl Parser {
         loop {
             if self.match_(&[TokenType::LeftParen]) {
                 expr = self.finish_call(expr?);
+            } else if self.match_(&[TokenType::Dot]) {
+                let name =
+                    self.consume(&TokenType::Identifier, "Expect property name after '.'.")?;
+                expr = Ok(Expr::Get {
+                    object: Box::new(expr?),
+                    name,
+                });
+            } else if self.match_(&[TokenType::LeftBracket]) {
+                let bracket = self.previous();
+                let index = self.expression()?;
+                self.consume(&TokenType::RightBracket, "Expect ']' after index.")?;
+                expr = Ok(Expr::Index {
+                    object: Box::new(expr?),
+                    bracket,
+                    index: Box::new(index),
+                });
+            } else if self.match_(&[TokenType::PlusPlus, TokenType::MinusMinus]) {
+                let op = self.previous();
+                expr = Ok(Expr::Increment {
+                    target: self.assignment_target(expr?, &op)?,
+                    op,
+                    prefix: false,
+                });
             } else {
                 break;
             }
@@ -424,7 +760,29 @@ impl Parser {
             return Ok(Expr::Variable(self.previous()));
         }
 
+        if self.match_(&[TokenType::Fun]) {
+            return self.lambda();
+        }
+
+        if self.match_(&[TokenType::LeftBracket]) {
+            let mut elements = Vec::new();
+            if !self.check(&TokenType::RightBracket) {
+                loop {
+                    elements.push(self.expression()?);
+                    if !self.match_(&[TokenType::Comma]) {
+                        break;
+                    }
+                }
+            }
+            self.consume(&TokenType::RightBracket, "Expect ']' after list elements.")?;
+            return Ok(Expr::List(elements));
+        }
+
         if self.match_(&[TokenType::LeftParen]) {
+            if self.is_arrow_lambda() {
+                return self.arrow_lambda();
+            }
+
             let expr = self.expression();
             let _ = self.consume(&TokenType::RightParen, "Expect ')' after expression.");
 
@@ -444,8 +802,10 @@ impl Parser {
         Err(self.error(self.peek(), message))
     }
 
-    fn error(&self, token: Token, message: &str) -> ParseError {
+    fn error(&mut self, token: Token, message: &str) -> ParseError {
+        self.had_error = true;
         Lox::token_error(&token, message);
+        self.errors.push((token, message.to_string()));
         ParseError
     }
 
@@ -459,7 +819,9 @@ impl Parser {
             }
 
             match self.peek().type_ {
-                Class | Fun | Var | For | If | While | Print | Return => return,
+                Class | Fun | Var | For | If | Import | While | Print | Return | Throw | Try => {
+                    return
+                }
                 _ => (),
             }
 
@@ -492,6 +854,13 @@ impl Parser {
         self.peek().type_ == *t
     }
 
+    fn check_next(&self, t: &TokenType) -> bool {
+        match self.tokens.get(self.current + 1) {
+            Some(token) => token.type_ == *t,
+            None => false,
+        }
+    }
+
     fn is_at_end(&self) -> bool {
         self.peek().type_ == TokenType::Eof
     }
@@ -505,6 +874,19 @@ impl Parser {
     }
 }
 
+/// Builds the function a lambda evaluates to, named `anonymous` and placed at
+/// its `fun` or `=>` token.
+fn lambda(keyword: Token, params: Vec<Param>, body: Vec<Stmt>) -> Expr {
+    let name = Token {
+        type_: TokenType::Identifier,
+        lexeme: "anonymous".to_string(),
+        literal: LitVal::Nil,
+        line: keyword.line,
+        column: keyword.column,
+    };
+    Expr::Lambda(Rc::new(Stmt::Function { name, params, body }))
+}
+
 #[cfg(test)]
 mod tests {
     use crate::scanner::Scanner;
@@ -519,6 +901,7 @@ mod tests {
                     type_: TokenType::Minus,
                     lexeme: "-".to_string(),
                     line: 1,
+                    column: 0,
                     literal: LitVal::NotExist,
                 },
                 right: Box::new(Expr::Literal(LitVal::Number(123.0))),
@@ -527,6 +910,7 @@ mod tests {
                 type_: TokenType::Star,
                 lexeme: "*".to_string(),
                 line: 1,
+                column: 0,
                 literal: LitVal::NotExist,
             },
             right: Box::new(Expr::Grouping {
@@ -546,7 +930,29 @@ mod tests {
 
         assert_eq!(
             expr.unwrap().stringify(),
-            "(+ (group (- 5.0 (group (- 3.0 1.0)))) (- 1.0))"
+            "(+ (group (- 5 (group (- 3 1)))) (- 1))"
+        );
+    }
+
+    #[test]
+    fn stringify_stmts() {
+        let source = "fun f(a, b = 1, ...c) { return a[0] += b; }
+            var g = (x) => x.y + x[0]++;
+            if (!a and b) print \"hi\"; else { --i; }
+            for (var i = 0; i < 2; i = i + 1) l[i] = f(i);
+            try { throw [1, 2.5]; } catch (e) {} finally { import \"m.lox\" as m; }";
+        let mut scanner = Scanner::new(source.to_string());
+        let stmts = Parser::new(scanner.scan_tokens().clone()).parse().unwrap();
+        let printed: Vec<String> = stmts.iter().map(Stmt::stringify).collect();
+        assert_eq!(
+            printed,
+            [
+                "(fun f (a (= b 1) (... c)) (return (+= ([] a 0) b)))",
+                "(var g (fun (x) (return (+ (. x y) (post++ ([] x 0))))))",
+                "(if (and (! a) b) (print \"hi\") (block (; (pre-- i))))",
+                "(block (var i 0) (while (< i 2) (block (; ([]= l i (call f i))) (; (= i (+ i 1))))))",
+                "(try (block (throw (list 1 2.5))) (catch e) (finally (import \"m.lox\" m)))",
+            ]
         );
     }
 }
@@ -0,0 +1,368 @@
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Instant;
+
+use anyhow::Result;
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+
+use crate::output::{diagnostic, with_diagnostics, Output};
+use crate::parser::Parser;
+use crate::scanner::{Scanner, KEYWORDS};
+use crate::stmt::Stmt;
+use crate::token::{Token, TokenType};
+use crate::types::LitVal;
+use crate::{Lox, HAD_ERROR};
+
+impl Lox {
+    /// Reads and runs lines until end of input. Ctrl-C discards the entry
+    /// being typed, and history is kept in `~/.rustlox_history`.
+    pub fn run_prompt(&mut self) -> Result<()> {
+        let mut editor = Editor::<LoxHelper, DefaultHistory>::new()?;
+        editor.set_helper(Some(LoxHelper { names: Vec::new() }));
+        let history = std::env::var_os("HOME").map(|home| PathBuf::from(home).join(HISTORY_FILE));
+        if let Some(history) = &history {
+            // There's no history yet on the first run.
+            let _ = editor.load_history(history);
+        }
+
+        let mut source = String::new();
+        loop {
+            if let Some(helper) = editor.helper_mut() {
+                helper.names = self.completion_names();
+            }
+            let prompt = if source.is_empty() { "> " } else { ".. " };
+            let line = match editor.readline(prompt) {
+                Ok(line) => line,
+                Err(ReadlineError::Interrupted) => {
+                    source.clear();
+                    continue;
+                }
+                Err(ReadlineError::Eof) => {
+                    if !source.is_empty() {
+                        self.run_line(source);
+                    }
+                    break;
+                }
+                Err(e) => return Err(e.into()),
+            };
+            if !line.trim().is_empty() {
+                editor.add_history_entry(line.as_str())?;
+            }
+            if source.is_empty() && line.trim_start().starts_with(':') {
+                self.run_command(line.trim());
+                continue;
+            }
+            source.push_str(&line);
+            source.push('\n');
+            if !is_incomplete(&source) {
+                self.run_line(std::mem::take(&mut source));
+            }
+        }
+
+        if let Some(history) = &history {
+            editor.save_history(history)?;
+        }
+        Ok(())
+    }
+
+    /// Keywords and global names, for Tab completion.
+    fn completion_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = KEYWORDS
+            .iter()
+            .map(|(keyword, _)| keyword.to_string())
+            .chain(self.interpreter.globals.borrow().values().keys().cloned())
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Runs a `:command`, with any argument after the first space.
+    fn run_command(&mut self, line: &str) {
+        let (command, argument) = line.split_once(' ').unwrap_or((line, ""));
+        let argument = argument.trim();
+        let diagnostics = Rc::clone(&self.diagnostics);
+        match command {
+            ":help" => self.say(format_args!("{}", HELP)),
+            ":reset" => self.reset(),
+            ":tokens" => {
+                self.print_tokens(argument);
+            }
+            ":ast" => {
+                self.print_ast(argument);
+            }
+            ":env" => self.dump_env(),
+            ":load" => match std::fs::read_to_string(argument) {
+                Ok(source) => {
+                    // Imports in the file resolve relative to it.
+                    let previous = self.path.replace(argument.to_string());
+                    self.run_line(source);
+                    self.path = previous;
+                }
+                Err(e) => with_diagnostics(&diagnostics, || {
+                    diagnostic(format_args!("Could not read '{}': {}.", argument, e))
+                }),
+            },
+            ":time" => {
+                let start = Instant::now();
+                self.run_line(argument.to_string());
+                self.say(format_args!("Took {:?}.", start.elapsed()));
+            }
+            ":type" => {
+                let value = with_diagnostics(&diagnostics, || {
+                    let mut scanner = Scanner::new(argument.to_string());
+                    let tokens = scanner.scan_tokens().clone();
+                    let expr = Parser::new(tokens).parse_expression().ok()?;
+                    let env = self.interpreter.env();
+                    self.interpreter
+                        .evaluate_in(&expr, env)
+                        .map_err(Lox::runtime_error)
+                        .ok()
+                });
+                if let Some(value) = value {
+                    self.say(format_args!("{}", value.type_name()));
+                }
+            }
+            _ => with_diagnostics(&diagnostics, || {
+                diagnostic(format_args!(
+                    "Unknown command '{}'. Type :help for a list.",
+                    command
+                ))
+            }),
+        }
+    }
+
+    /// Prints every binding from the innermost scope outwards. Natives are
+    /// only counted, since there are dozens of them.
+    fn dump_env(&mut self) {
+        let mut env = Some(self.interpreter.env());
+        let mut built_ins = 0;
+        while let Some(scope) = env {
+            let scope = scope.borrow();
+            let mut names: Vec<_> = scope.values().iter().collect();
+            names.sort_by(|a, b| a.0.cmp(b.0));
+            for (name, value) in names {
+                if matches!(value, LitVal::Native(_)) || scope.is_constant(name) {
+                    built_ins += 1;
+                } else {
+                    self.say(format_args!("{} = {}", name, value));
+                }
+            }
+            env = scope.enclosing.clone();
+        }
+        self.say(format_args!(
+            "({} built-in functions and constants)",
+            built_ins
+        ));
+    }
+
+    /// Runs one complete REPL entry, printing its value if it's a bare expression.
+    fn run_line(&mut self, source: String) {
+        if let Some(print) = echo(&source) {
+            let diagnostics = Rc::clone(&self.diagnostics);
+            with_diagnostics(&diagnostics, || self.interpreter.interpret(vec![print]));
+            return;
+        }
+        if let Err(e) = self.run(source) {
+            with_diagnostics(&self.diagnostics, || diagnostic(format_args!("{}", e)));
+            unsafe {
+                HAD_ERROR = true;
+            }
+        }
+    }
+}
+
+const HISTORY_FILE: &str = ".rustlox_history";
+
+/// Completes the word before the cursor from a sorted list of names.
+struct LoxHelper {
+    names: Vec<String>,
+}
+
+impl Completer for LoxHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(complete(&self.names, line, pos))
+    }
+}
+
+impl Hinter for LoxHelper {
+    type Hint = String;
+}
+
+impl Highlighter for LoxHelper {}
+
+impl Validator for LoxHelper {}
+
+impl Helper for LoxHelper {}
+
+/// The start of the identifier ending at byte `pos` of `line`, and the names
+/// it's a prefix of.
+fn complete(names: &[String], line: &str, pos: usize) -> (usize, Vec<String>) {
+    let start = line[..pos]
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| c.is_alphanumeric() || *c == '_')
+        .last()
+        .map_or(pos, |(i, _)| i);
+    let word = &line[start..pos];
+    if word.is_empty() {
+        return (pos, Vec::new());
+    }
+    let candidates = names
+        .iter()
+        .filter(|name| name.starts_with(word))
+        .cloned()
+        .collect();
+    (start, candidates)
+}
+
+const HELP: &str = "\
+:help          Show this list.
+:reset         Forget all definitions.
+:tokens <src>  Show the tokens <src> scans to.
+:ast <src>     Show the statements <src> parses to.
+:env           Show the variables in scope.
+:load <file>   Run <file> in this session.
+:time <src>    Run <src> and show how long it took.
+:type <expr>   Show the type of <expr>'s value.";
+
+/// A statement printing `source`'s value, if `source` is a single expression
+/// without a trailing `;`.
+fn echo(source: &str) -> Option<Stmt> {
+    // Whatever doesn't parse here goes through `run`, which reports its errors.
+    let silent: Output = Rc::new(RefCell::new(std::io::sink()));
+    with_diagnostics(&silent, || {
+        let mut scanner = Scanner::new(source.to_string());
+        let tokens = scanner.scan_tokens().clone();
+        if scanner.had_error || tokens.len() < 2 {
+            return None;
+        }
+        if matches!(
+            tokens[tokens.len() - 2].type_,
+            TokenType::Semicolon | TokenType::RightBrace
+        ) {
+            return None;
+        }
+        // The echo is printed as if the entry had been written `print entry;`.
+        let keyword = Token {
+            type_: TokenType::Print,
+            lexeme: "print".to_string(),
+            literal: LitVal::Nil,
+            ..tokens[0].clone()
+        };
+        let value = Parser::new(tokens).parse_expression().ok()?;
+        Some(Stmt::Print { keyword, value })
+    })
+}
+
+/// Whether `source` stops inside a string or an unclosed bracket, so the REPL
+/// should read another line before running it.
+fn is_incomplete(source: &str) -> bool {
+    let mut depth = 0;
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            // Lox strings have no escapes, so the next quote always ends one.
+            '"' if !chars.any(|c| c == '"') => return true,
+            '/' if chars.peek() == Some(&'/') => {
+                chars.any(|c| c == '\n');
+            }
+            '(' | '{' | '[' => depth += 1,
+            ')' | '}' | ']' => depth -= 1,
+            _ => (),
+        }
+    }
+    depth > 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Buffer;
+
+    #[test]
+    fn incomplete() {
+        assert!(is_incomplete("fun f() {\n"));
+        assert!(is_incomplete("print (1 +\n"));
+        assert!(is_incomplete("var s = \"a\n"));
+        assert!(is_incomplete("var l = [1, // ]\n"));
+        assert!(!is_incomplete("fun f() {\n}\n"));
+        assert!(!is_incomplete("print \"{\";\n"));
+        assert!(!is_incomplete("print 1; // (\n"));
+        // Too many closing brackets is an error for the parser to report.
+        assert!(!is_incomplete("}\n"));
+    }
+
+    #[test]
+    fn completion() {
+        let names = ["clock", "print", "pow", "var"].map(String::from);
+        assert_eq!(
+            complete(&names, "var x = p", 9),
+            (8, vec!["print".to_string(), "pow".to_string()])
+        );
+        assert_eq!(complete(&names, "cl(1)", 2), (0, vec!["clock".to_string()]));
+        assert_eq!(complete(&names, "x + ", 4), (4, vec![]));
+    }
+
+    #[test]
+    fn bare_expressions() {
+        let out = Buffer::default();
+        let mut lox = Lox::new();
+        lox.set_output(out.clone());
+        lox.set_diagnostics(std::io::sink());
+        lox.run_line("var a = 1;\n".to_string());
+        lox.run_line("a + 1\n".to_string());
+        lox.run_line("a = 5\n".to_string());
+        lox.run_line("\"s\"\n".to_string());
+        lox.run_line("print a;\n".to_string());
+        lox.run_line("fun f() {\n  return a;\n}\n".to_string());
+        lox.run_line("f()\n".to_string());
+        lox.run_line("a +\n".to_string());
+        lox.run_line("\n".to_string());
+        assert_eq!(out.contents(), "2\n5\ns\n5\n5\n");
+    }
+
+    #[test]
+    fn commands() {
+        let (out, err) = (Buffer::default(), Buffer::default());
+        let mut lox = Lox::new();
+        lox.set_output(out.clone());
+        lox.set_diagnostics(err.clone());
+        lox.run_line("var a = 1; var b = [a];".to_string());
+        lox.run_command(":env");
+        lox.run_command(":type b");
+        lox.run_command(":type a +");
+        lox.run_command(":tokens a = 1;");
+        lox.run_command(":nope");
+        lox.run_command(":reset");
+        lox.run_command(":type a");
+        assert_eq!(
+            out.contents(),
+            format!(
+                "a = 1\nb = [1]\n({} built-in functions and constants)\nlist\n{}\n",
+                Lox::new().interpreter.globals.borrow().values().len(),
+                "IDENTIFIER a null\nEQUAL = null\nNUMBER 1 1\nSEMICOLON ; null\nEOF  null"
+            )
+        );
+        assert_eq!(
+            err.contents(),
+            "[line 1] Error at end: Expect expression.\n\
+             Unknown command ':nope'. Type :help for a list.\n\
+             Undefined variable 'a'.\n[line 1]\n"
+        );
+    }
+}
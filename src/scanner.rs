@@ -1,29 +1,102 @@
 use crate::token::{Token, TokenType};
 use crate::{types, Lox};
-use types::LitVal;
+use types::{Int, LitVal, Number};
+
+/// Reserved words and the tokens they scan to.
+pub const KEYWORDS: &[(&str, TokenType)] = &[
+    ("and", TokenType::And),
+    ("catch", TokenType::Catch),
+    ("class", TokenType::Class),
+    ("else", TokenType::Else),
+    ("false", TokenType::False),
+    ("finally", TokenType::Finally),
+    ("for", TokenType::For),
+    ("fun", TokenType::Fun),
+    ("if", TokenType::If),
+    ("import", TokenType::Import),
+    ("nil", TokenType::Nil),
+    ("or", TokenType::Or),
+    ("print", TokenType::Print),
+    ("return", TokenType::Return),
+    ("super", TokenType::Super),
+    ("this", TokenType::This),
+    ("throw", TokenType::Throw),
+    ("true", TokenType::True),
+    ("try", TokenType::Try),
+    ("var", TokenType::Var),
+    ("while", TokenType::While),
+];
 
 pub struct Scanner {
-    source: String,
+    /// The source as chars, so `start` and `current` index characters, not bytes.
+    source: Vec<char>,
     tokens: Vec<Token>,
     start: usize,
     current: usize,
     line: usize,
+    /// Where the current line starts in `source`.
+    line_start: usize,
+    /// The column `start` is at.
+    column: usize,
+    /// Whether comments become `Comment` tokens instead of being skipped.
+    keep_comments: bool,
+    /// Whether errors only set `had_error` instead of being reported.
+    silent: bool,
+    pub had_error: bool,
 }
 
 impl Scanner {
     pub fn new(source: String) -> Self {
         Scanner {
-            source,
+            source: source.chars().collect(),
             tokens: Vec::new(),
             start: 0,
             current: 0,
             line: 1,
+            line_start: 0,
+            column: 0,
+            keep_comments: false,
+            silent: false,
+            had_error: false,
+        }
+    }
+
+    /// A scanner that keeps comments, for tools that must not lose them.
+    pub fn with_comments(source: String) -> Self {
+        Scanner {
+            keep_comments: true,
+            ..Scanner::new(source)
+        }
+    }
+
+    /// Parses the whole of `text` as a number literal, optionally signed, so
+    /// that `num()` accepts the same syntax as source code.
+    pub(crate) fn parse_number(text: &str) -> Option<LitVal> {
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text.strip_prefix('+').unwrap_or(text)),
+        };
+        let first = digits.chars().next().filter(char::is_ascii_digit)?;
+        let mut scanner = Scanner {
+            silent: true,
+            ..Scanner::new(digits.to_string())
+        };
+        scanner.advance();
+        scanner.number(first);
+        if scanner.had_error || !scanner.is_at_end() {
+            return None;
+        }
+        match scanner.tokens.pop()?.literal {
+            LitVal::Int(i) if negative => i.checked_neg().map(LitVal::Int),
+            LitVal::Number(x) if negative => Some(LitVal::Number(-x)),
+            literal => Some(literal),
         }
     }
 
     pub fn scan_tokens(&mut self) -> &Vec<Token> {
         while !self.is_at_end() {
             self.start = self.current;
+            self.column = self.current - self.line_start;
             self.scan_token();
         }
 
@@ -32,6 +105,7 @@ impl Scanner {
             lexeme: String::new(),
             literal: LitVal::Nil,
             line: self.line,
+            column: self.current - self.line_start,
         });
         &self.tokens
     }
@@ -42,12 +116,50 @@ impl Scanner {
             ')' => self.add_token_default(TokenType::RightParen),
             '{' => self.add_token_default(TokenType::LeftBrace),
             '}' => self.add_token_default(TokenType::RightBrace),
+            '[' => self.add_token_default(TokenType::LeftBracket),
+            ']' => self.add_token_default(TokenType::RightBracket),
             ',' => self.add_token_default(TokenType::Comma),
-            '.' => self.add_token_default(TokenType::Dot),
-            '-' => self.add_token_default(TokenType::Minus),
-            '+' => self.add_token_default(TokenType::Plus),
+            '.' => {
+                if self.peek() == '.' && self.peek_next() == '.' {
+                    self.advance();
+                    self.advance();
+                    self.add_token_default(TokenType::DotDotDot);
+                } else {
+                    self.add_token_default(TokenType::Dot);
+                }
+            }
+            '-' => {
+                if self.match_('=') {
+                    self.add_token_default(TokenType::MinusEqual);
+                } else if self.match_('-') {
+                    self.add_token_default(TokenType::MinusMinus);
+                } else {
+                    self.add_token_default(TokenType::Minus);
+                }
+            }
+            '+' => {
+                if self.match_('=') {
+                    self.add_token_default(TokenType::PlusEqual);
+                } else if self.match_('+') {
+                    self.add_token_default(TokenType::PlusPlus);
+                } else {
+                    self.add_token_default(TokenType::Plus);
+                }
+            }
             ';' => self.add_token_default(TokenType::Semicolon),
-            '*' => self.add_token_default(TokenType::Star),
+            '%' => self.add_token_default(TokenType::Percent),
+            '&' => self.add_token_default(TokenType::Ampersand),
+            '|' => self.add_token_default(TokenType::Pipe),
+            '^' => self.add_token_default(TokenType::Caret),
+            '*' => {
+                if self.match_('*') {
+                    self.add_token_default(TokenType::StarStar);
+                } else if self.match_('=') {
+                    self.add_token_default(TokenType::StarEqual);
+                } else {
+                    self.add_token_default(TokenType::Star);
+                }
+            }
 
             '!' => {
                 if self.match_('=') {
@@ -59,6 +171,8 @@ impl Scanner {
             '=' => {
                 if self.match_('=') {
                     self.add_token_default(TokenType::EqualEqual);
+                } else if self.match_('>') {
+                    self.add_token_default(TokenType::Arrow);
                 } else {
                     self.add_token_default(TokenType::Equal);
                 }
@@ -66,6 +180,8 @@ impl Scanner {
             '<' => {
                 if self.match_('=') {
                     self.add_token_default(TokenType::LessEqual);
+                } else if self.match_('<') {
+                    self.add_token_default(TokenType::LessLess);
                 } else {
                     self.add_token_default(TokenType::Less);
                 }
@@ -73,6 +189,8 @@ impl Scanner {
             '>' => {
                 if self.match_('=') {
                     self.add_token_default(TokenType::GreaterEqual);
+                } else if self.match_('>') {
+                    self.add_token_default(TokenType::GreaterGreater);
                 } else {
                     self.add_token_default(TokenType::Greater);
                 }
@@ -82,21 +200,37 @@ impl Scanner {
                     while self.peek() != '\n' && !self.is_at_end() {
                         self.advance();
                     }
+                    if self.keep_comments {
+                        self.add_token_default(TokenType::Comment);
+                    }
+                } else if self.match_('=') {
+                    self.add_token_default(TokenType::SlashEqual);
                 } else {
                     self.add_token_default(TokenType::Slash);
                 }
             }
+            '~' => {
+                // Floor division can't be `//`, which already starts a comment.
+                if self.match_('/') {
+                    self.add_token_default(TokenType::TildeSlash);
+                } else {
+                    self.add_token_default(TokenType::Tilde);
+                }
+            }
             ' ' | '\r' | '\t' => (),
-            '\n' => self.line += 1,
+            '\n' => {
+                self.line += 1;
+                self.line_start = self.current;
+            }
             '"' => self.string(),
 
             c => {
                 if c.is_ascii_digit() {
-                    self.number();
+                    self.number(c);
                 } else if self.is_alpha(c) {
                     self.identifier();
                 } else {
-                    Lox::error(self.line, "Unexpected character.");
+                    self.error("Unexpected character.");
                 }
             }
         };
@@ -107,26 +241,11 @@ impl Scanner {
             self.advance();
         }
 
-        let text = self.source[self.start..self.current].to_string();
-        let token = match text.as_str() {
-            "and" => TokenType::And,
-            "class" => TokenType::Class,
-            "else" => TokenType::Else,
-            "false" => TokenType::False,
-            "fun" => TokenType::Fun,
-            "for" => TokenType::For,
-            "if" => TokenType::If,
-            "nil" => TokenType::Nil,
-            "or" => TokenType::Or,
-            "print" => TokenType::Print,
-            "return" => TokenType::Return,
-            "super" => TokenType::Super,
-            "this" => TokenType::This,
-            "true" => TokenType::True,
-            "var" => TokenType::Var,
-            "while" => TokenType::While,
-            _ => TokenType::Identifier,
-        };
+        let text: String = self.source[self.start..self.current].iter().collect();
+        let token = KEYWORDS
+            .iter()
+            .find(|(keyword, _)| *keyword == text)
+            .map_or(TokenType::Identifier, |(_, token)| token.clone());
 
         self.add_token(token, LitVal::String(text));
     }
@@ -135,12 +254,13 @@ impl Scanner {
         while self.peek() != '"' && !self.is_at_end() {
             if self.peek() == '\n' {
                 self.line += 1;
+                self.line_start = self.current + 1;
             }
             self.advance();
         }
 
         if self.is_at_end() {
-            Lox::error(self.line, "Unterminated string.");
+            self.error("Unterminated string.");
             return;
         }
 
@@ -148,15 +268,24 @@ impl Scanner {
         self.advance();
 
         // Trim the surrounding quotes.
-        let value = self.source[(self.start + 1)..(self.current - 1)].to_string();
+        let value = self.source[(self.start + 1)..(self.current - 1)]
+            .iter()
+            .collect();
         self.add_token(TokenType::String, LitVal::String(value));
     }
 
+    fn error(&mut self, message: &str) {
+        self.had_error = true;
+        if !self.silent {
+            Lox::error(self.line, message);
+        }
+    }
+
     fn match_(&mut self, expected: char) -> bool {
         if self.is_at_end() {
             return false;
         }
-        if self.source.chars().nth(self.current).unwrap() != expected {
+        if self.source[self.current] != expected {
             return false;
         }
         self.current += 1;
@@ -167,7 +296,7 @@ impl Scanner {
         if self.is_at_end() {
             '\0'
         } else {
-            self.source.chars().nth(self.current).unwrap()
+            self.source[self.current]
         }
     }
 
@@ -175,7 +304,7 @@ impl Scanner {
         if self.current + 1 >= self.source.len() {
             '\0'
         } else {
-            self.source.chars().nth(self.current + 1).unwrap()
+            self.source[self.current + 1]
         }
     }
 
@@ -187,36 +316,147 @@ impl Scanner {
         self.is_alpha(c) || c.is_ascii_digit()
     }
 
-    fn number(&mut self) {
-        while self.peek().is_ascii_digit() {
-            self.advance();
+    fn number(&mut self, first: char) {
+        if first == '0' {
+            let radix = match self.peek() {
+                'x' | 'X' => Some((16, "hexadecimal")),
+                'o' | 'O' => Some((8, "octal")),
+                'b' | 'B' => Some((2, "binary")),
+                _ => None,
+            };
+            if let Some((radix, name)) = radix {
+                // Consume the prefix.
+                self.advance();
+                return self.radix_number(radix, name);
+            }
         }
+
+        let mut ok = self.digits(10, true);
+        let mut is_float = false;
         // Look for a fractional part.
         if self.peek() == '.' && self.peek_next().is_ascii_digit() {
             // Consume the "."
             self.advance();
 
-            while self.peek().is_ascii_digit() {
+            is_float = true;
+            ok &= self.digits(10, false);
+        }
+        // Look for an exponent.
+        if self.peek() == 'e' || self.peek() == 'E' {
+            is_float = true;
+            self.advance();
+            if self.peek() == '+' || self.peek() == '-' {
                 self.advance();
             }
+            if self.peek().is_ascii_digit() {
+                ok &= self.digits(10, false);
+            } else {
+                self.skip_alpha_numeric();
+                self.error("Expect digits in exponent.");
+                return;
+            }
+        }
+        if !self.check_number_end("number") || !ok {
+            return;
+        }
+
+        let text: String = self.source[self.start..self.current]
+            .iter()
+            .filter(|c| **c != '_')
+            .collect();
+        if !is_float {
+            match text.parse::<Int>() {
+                Ok(value) => self.add_token(TokenType::Number, LitVal::Int(value)),
+                Err(_) => self.error("Number literal is out of range."),
+            }
+            return;
+        }
+        match text.parse::<Number>() {
+            Ok(value) if value.is_finite() => {
+                self.add_token(TokenType::Number, LitVal::Number(value))
+            }
+            _ => self.error("Number literal is out of range."),
+        }
+    }
+
+    fn radix_number(&mut self, radix: u32, name: &str) {
+        if !self.peek().is_digit(radix) {
+            self.skip_alpha_numeric();
+            self.error(&format!("Expect digits after {name} prefix."));
+            return;
+        }
+        let ok = self.digits(radix, false);
+        if !self.check_number_end(name) || !ok {
+            return;
+        }
+
+        // Skip the two character prefix and any separators.
+        let text: String = self.source[(self.start + 2)..self.current]
+            .iter()
+            .filter(|c| **c != '_')
+            .collect();
+        match Int::from_str_radix(&text, radix) {
+            Ok(value) => self.add_token(TokenType::Number, LitVal::Int(value)),
+            Err(_) => self.error("Number literal is out of range."),
+        }
+    }
+
+    /// Consumes a run of digits in `radix`, where single `_` separators may
+    /// appear between digits. `after_digit` is true when the caller has already
+    /// consumed a digit that the run continues.
+    fn digits(&mut self, radix: u32, after_digit: bool) -> bool {
+        let mut ok = true;
+        let mut prev_digit = after_digit;
+        while self.peek().is_digit(radix) || self.peek() == '_' {
+            let c = self.advance();
+            if c == '_' && !prev_digit {
+                ok = false;
+            }
+            prev_digit = c != '_';
+        }
+        if !ok || !prev_digit {
+            self.error("Digit separator '_' must be between digits.");
+            return false;
+        }
+        true
+    }
+
+    /// Reports an error if the literal runs straight into letters or digits
+    /// that don't belong to it, like `0b102` or `12abc`.
+    fn check_number_end(&mut self, name: &str) -> bool {
+        let c = self.peek();
+        if !self.is_alpha_numeric(c) {
+            return true;
+        }
+        self.skip_alpha_numeric();
+        if c.is_ascii_hexdigit() {
+            self.error(&format!("Invalid digit '{c}' in {name} literal."));
+        } else {
+            self.error(&format!("Invalid character '{c}' in {name} literal."));
+        }
+        false
+    }
+
+    fn skip_alpha_numeric(&mut self) {
+        while self.is_alpha_numeric(self.peek()) {
+            self.advance();
         }
-        let value = self.source[self.start..self.current].parse().unwrap();
-        self.add_token(TokenType::Number, LitVal::Number(value));
     }
 
     fn advance(&mut self) -> char {
-        let c = self.source.chars().nth(self.current).unwrap();
+        let c = self.source[self.current];
         self.current += 1;
         c
     }
 
     fn add_token(&mut self, token: TokenType, literal: LitVal) {
-        let text = self.source[self.start..self.current].to_string();
+        let text = self.source[self.start..self.current].iter().collect();
         self.tokens.push(Token {
             type_: token,
             lexeme: text,
             literal,
             line: self.line,
+            column: self.column,
         });
     }
 
@@ -262,4 +502,29 @@ EOF  null"
             assert_eq!(&token.to_string(), e);
         }
     }
+
+    #[test]
+    fn numbers() {
+        let mut scanner = Scanner::new(
+            "123 1_000 0.5 1.5e-3 2E+2 0xFF 0b1010 0o17 0x_1 1__0 1_ 0b102 12abc 1e 0x".to_string(),
+        );
+        let tokens = scanner.scan_tokens();
+
+        let expected: Vec<_> = "NUMBER 123 123
+NUMBER 1_000 1000
+NUMBER 0.5 0.5
+NUMBER 1.5e-3 0.0015
+NUMBER 2E+2 200.0
+NUMBER 0xFF 255
+NUMBER 0b1010 10
+NUMBER 0o17 15
+EOF  null"
+            .split('\n')
+            .collect();
+
+        assert_eq!(tokens.len(), expected.len());
+        for (token, e) in zip(tokens, expected) {
+            assert_eq!(&token.to_string(), e);
+        }
+    }
 }
@@ -1,32 +1,183 @@
 use expr::Expr;
 
-use crate::{expr, token::Token};
+use crate::{
+    expr::{self, stringify_params},
+    token::Token,
+};
 
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub enum Stmt {
-    Block(Vec<Stmt>),
-    Expr(Expr),
+    /// `brace` is the `{` token, or the `for` keyword for the blocks a `for`
+    /// loop desugars into.
+    Block {
+        brace: Token,
+        stmts: Vec<Stmt>,
+    },
+    /// `semicolon` gives the line of an expression made only of literals.
+    Expr {
+        expr: Expr,
+        semicolon: Token,
+    },
     Function {
         name: Token,
-        params: Vec<Token>,
+        params: Vec<Param>,
         body: Vec<Stmt>,
     },
     If {
+        keyword: Token,
         condition: Expr,
         then_branch: Box<Stmt>,
         else_branch: Option<Box<Stmt>>,
     },
-    Print(Expr),
+    /// `import "path";` or `import "path" as alias;`.
+    Import {
+        keyword: Token,
+        path: Token,
+        alias: Option<Token>,
+    },
+    Print {
+        keyword: Token,
+        value: Expr,
+    },
     Return {
         keyword: Token,
         value: Expr,
     },
+    Throw {
+        keyword: Token,
+        value: Expr,
+    },
+    /// `try { } catch (name) { } finally { }`, where at least one of `catch`
+    /// and `finally` is present.
+    Try {
+        keyword: Token,
+        body: Vec<Stmt>,
+        catch: Option<(Token, Vec<Stmt>)>,
+        finally: Option<Vec<Stmt>>,
+    },
     Var {
         name: Token,
         initializer: Expr,
     },
+    /// `keyword` is the `while` token, or `for` for a desugared `for` loop.
     While {
+        keyword: Token,
         condition: Expr,
         body: Box<Stmt>,
     },
 }
+
+impl Stmt {
+    /// The line the statement starts on.
+    pub fn line(&self) -> usize {
+        match self {
+            Stmt::Block { brace, .. } => brace.line,
+            Stmt::Expr { expr, semicolon } => expr.line().unwrap_or(semicolon.line),
+            Stmt::Function { name, .. } | Stmt::Var { name, .. } => name.line,
+            Stmt::If { keyword, .. }
+            | Stmt::Import { keyword, .. }
+            | Stmt::Print { keyword, .. }
+            | Stmt::Return { keyword, .. }
+            | Stmt::Throw { keyword, .. }
+            | Stmt::Try { keyword, .. }
+            | Stmt::While { keyword, .. } => keyword.line,
+        }
+    }
+
+    /// The line and column of the token that introduces the statement, which
+    /// no other statement shares.
+    pub fn position(&self) -> (usize, usize) {
+        let token = match self {
+            Stmt::Block { brace, .. } => brace,
+            Stmt::Expr { semicolon, .. } => semicolon,
+            Stmt::Function { name, .. } | Stmt::Var { name, .. } => name,
+            Stmt::If { keyword, .. }
+            | Stmt::Import { keyword, .. }
+            | Stmt::Print { keyword, .. }
+            | Stmt::Return { keyword, .. }
+            | Stmt::Throw { keyword, .. }
+            | Stmt::Try { keyword, .. }
+            | Stmt::While { keyword, .. } => keyword,
+        };
+        (token.line, token.column)
+    }
+
+    /// Prints the statement as an S-expression, e.g. `(var a (+ 1 2))`.
+    pub fn stringify(&self) -> String {
+        match self {
+            Stmt::Block { stmts, .. } => format!("(block{})", stringify_block(stmts)),
+            Stmt::Expr { expr, .. } => format!("(; {})", expr.stringify()),
+            Stmt::Function { name, params, body } => format!(
+                "(fun {} {}{})",
+                name.lexeme,
+                stringify_params(params),
+                stringify_block(body)
+            ),
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => match else_branch {
+                Some(else_branch) => format!(
+                    "(if {} {} {})",
+                    condition.stringify(),
+                    then_branch.stringify(),
+                    else_branch.stringify()
+                ),
+                None => format!("(if {} {})", condition.stringify(), then_branch.stringify()),
+            },
+            Stmt::Import { path, alias, .. } => match alias {
+                Some(alias) => format!("(import {} {})", path.lexeme, alias.lexeme),
+                None => format!("(import {})", path.lexeme),
+            },
+            Stmt::Print { value, .. } => format!("(print {})", value.stringify()),
+            Stmt::Return { value, .. } => format!("(return {})", value.stringify()),
+            Stmt::Throw { value, .. } => format!("(throw {})", value.stringify()),
+            Stmt::Try {
+                body,
+                catch,
+                finally,
+                ..
+            } => {
+                let mut s = format!("(try (block{})", stringify_block(body));
+                if let Some((name, body)) = catch {
+                    s.push_str(&format!(
+                        " (catch {}{})",
+                        name.lexeme,
+                        stringify_block(body)
+                    ));
+                }
+                if let Some(body) = finally {
+                    s.push_str(&format!(" (finally{})", stringify_block(body)));
+                }
+                s.push(')');
+                s
+            }
+            Stmt::Var { name, initializer } => {
+                format!("(var {} {})", name.lexeme, initializer.stringify())
+            }
+            Stmt::While {
+                condition, body, ..
+            } => {
+                format!("(while {} {})", condition.stringify(), body.stringify())
+            }
+        }
+    }
+}
+
+/// Each statement preceded by a space, for the end of an S-expression.
+fn stringify_block(stmts: &[Stmt]) -> String {
+    stmts
+        .iter()
+        .map(|stmt| format!(" {}", stmt.stringify()))
+        .collect()
+}
+
+/// A function parameter: `name`, `name = default` or `...name`.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct Param {
+    pub name: Token,
+    pub default: Option<Expr>,
+    pub rest: bool,
+}
@@ -7,6 +7,8 @@ pub enum TokenType {
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Comma,
     Dot,
     Minus,
@@ -14,6 +16,11 @@ pub enum TokenType {
     Semicolon,
     Slash,
     Star,
+    Percent,
+    Ampersand,
+    Pipe,
+    Caret,
+    Tilde,
 
     // One or two character tokens.
     Bang,
@@ -24,6 +31,18 @@ pub enum TokenType {
     GreaterEqual,
     Less,
     LessEqual,
+    StarStar,
+    TildeSlash,
+    LessLess,
+    GreaterGreater,
+    PlusEqual,
+    MinusEqual,
+    StarEqual,
+    SlashEqual,
+    PlusPlus,
+    MinusMinus,
+    Arrow,
+    DotDotDot,
 
     // Literals.
     Identifier,
@@ -38,17 +57,24 @@ pub enum TokenType {
     Fun,
     For,
     If,
+    Import,
     Nil,
     Or,
     Print,
     Return,
     Super,
     This,
+    Throw,
     True,
+    Try,
+    Catch,
+    Finally,
     Var,
     While,
 
     Eof,
+    /// A `//` comment, only produced by `Scanner::with_comments`.
+    Comment,
     Colon,
     Question,
     Break,
@@ -60,6 +86,8 @@ pub struct Token {
     pub lexeme: String,
     pub literal: LitVal,
     pub line: usize,
+    /// How many characters precede the token on the line it starts on.
+    pub column: usize,
 }
 
 impl Default for Token {
@@ -69,6 +97,7 @@ impl Default for Token {
             lexeme: String::new(),
             literal: LitVal::NotExist,
             line: 0,
+            column: 0,
         }
     }
 }
@@ -89,6 +118,25 @@ impl std::fmt::Display for TokenType {
             TokenType::Semicolon => f.write_str(";"),
             TokenType::Slash => f.write_str("/"),
             TokenType::Star => f.write_str("*"),
+            TokenType::Percent => f.write_str("%"),
+            TokenType::StarStar => f.write_str("**"),
+            TokenType::TildeSlash => f.write_str("~/"),
+            TokenType::Ampersand => f.write_str("&"),
+            TokenType::Pipe => f.write_str("|"),
+            TokenType::Caret => f.write_str("^"),
+            TokenType::Tilde => f.write_str("~"),
+            TokenType::LessLess => f.write_str("<<"),
+            TokenType::GreaterGreater => f.write_str(">>"),
+            TokenType::PlusEqual => f.write_str("+="),
+            TokenType::MinusEqual => f.write_str("-="),
+            TokenType::StarEqual => f.write_str("*="),
+            TokenType::SlashEqual => f.write_str("/="),
+            TokenType::PlusPlus => f.write_str("++"),
+            TokenType::MinusMinus => f.write_str("--"),
+            TokenType::Arrow => f.write_str("=>"),
+            TokenType::DotDotDot => f.write_str("..."),
+            TokenType::LeftBracket => f.write_str("["),
+            TokenType::RightBracket => f.write_str("]"),
             TokenType::Bang => f.write_str("!"),
             TokenType::BangEqual => f.write_str("!="),
             TokenType::Equal => f.write_str("="),
@@ -108,16 +156,22 @@ impl std::fmt::Display for TokenType {
             TokenType::Fun => f.write_str("fun"),
             TokenType::For => f.write_str("for"),
             TokenType::If => f.write_str("if"),
+            TokenType::Import => f.write_str("import"),
             TokenType::Nil => f.write_str("nil"),
             TokenType::Or => f.write_str("or"),
             TokenType::Print => f.write_str("print"),
             TokenType::Return => f.write_str("return"),
             TokenType::Super => f.write_str("super"),
             TokenType::This => f.write_str("this"),
+            TokenType::Throw => f.write_str("throw"),
+            TokenType::Try => f.write_str("try"),
+            TokenType::Catch => f.write_str("catch"),
+            TokenType::Finally => f.write_str("finally"),
             TokenType::True => f.write_str("true"),
             TokenType::Var => f.write_str("var"),
             TokenType::While => f.write_str("while"),
             TokenType::Eof => f.write_str("\\d"),
+            TokenType::Comment => f.write_str("//"),
         }
     }
 }
@@ -136,6 +190,25 @@ impl std::fmt::Display for Token {
             TokenType::Semicolon => write!(f, "SEMICOLON {} null", self.type_),
             TokenType::Slash => write!(f, "SLASH {} null", self.type_),
             TokenType::Star => write!(f, "STAR {} null", self.type_),
+            TokenType::Percent => write!(f, "PERCENT {} null", self.type_),
+            TokenType::StarStar => write!(f, "STAR_STAR {} null", self.type_),
+            TokenType::TildeSlash => write!(f, "TILDE_SLASH {} null", self.type_),
+            TokenType::Ampersand => write!(f, "AMPERSAND {} null", self.type_),
+            TokenType::Pipe => write!(f, "PIPE {} null", self.type_),
+            TokenType::Caret => write!(f, "CARET {} null", self.type_),
+            TokenType::Tilde => write!(f, "TILDE {} null", self.type_),
+            TokenType::LessLess => write!(f, "LESS_LESS {} null", self.type_),
+            TokenType::GreaterGreater => write!(f, "GREATER_GREATER {} null", self.type_),
+            TokenType::PlusEqual => write!(f, "PLUS_EQUAL {} null", self.type_),
+            TokenType::MinusEqual => write!(f, "MINUS_EQUAL {} null", self.type_),
+            TokenType::StarEqual => write!(f, "STAR_EQUAL {} null", self.type_),
+            TokenType::SlashEqual => write!(f, "SLASH_EQUAL {} null", self.type_),
+            TokenType::PlusPlus => write!(f, "PLUS_PLUS {} null", self.type_),
+            TokenType::MinusMinus => write!(f, "MINUS_MINUS {} null", self.type_),
+            TokenType::Arrow => write!(f, "ARROW {} null", self.type_),
+            TokenType::DotDotDot => write!(f, "DOT_DOT_DOT {} null", self.type_),
+            TokenType::LeftBracket => write!(f, "LEFT_BRACKET {} null", self.type_),
+            TokenType::RightBracket => write!(f, "RIGHT_BRACKET {} null", self.type_),
             TokenType::Bang => write!(f, "BANG {} null", self.type_),
             TokenType::BangEqual => write!(f, "BANG_EQUAL {} null", self.type_),
             TokenType::Equal => write!(f, "EQUAL {} null", self.type_),
@@ -162,13 +235,14 @@ impl std::fmt::Display for Token {
             }
             TokenType::Number => {
                 let n = match &self.literal {
+                    LitVal::Int(i) => return write!(f, "NUMBER {} {}", self.lexeme, i),
                     LitVal::Number(n) => n,
                     _ => panic!(""),
                 };
                 if *n == n.floor() {
-                    write!(f, "NUMBER {} {}.0", n, n)
+                    write!(f, "NUMBER {} {}.0", self.lexeme, n)
                 } else {
-                    write!(f, "NUMBER {} {}", n, n)
+                    write!(f, "NUMBER {} {}", self.lexeme, n)
                 }
             }
             TokenType::And => write!(f, "AND {} null", self.type_),
@@ -178,12 +252,17 @@ impl std::fmt::Display for Token {
             TokenType::Fun => write!(f, "FUN {} null", self.type_),
             TokenType::For => write!(f, "FOR {} null", self.type_),
             TokenType::If => write!(f, "IF {} null", self.type_),
+            TokenType::Import => write!(f, "IMPORT {} null", self.type_),
             TokenType::Nil => write!(f, "NIL {} null", self.type_),
             TokenType::Or => write!(f, "OR {} null", self.type_),
             TokenType::Print => write!(f, "PRINT {} null", self.type_),
             TokenType::Return => write!(f, "RETURN {} null", self.type_),
             TokenType::Super => write!(f, "SUPER {} null", self.type_),
             TokenType::This => write!(f, "THIS {} null", self.type_),
+            TokenType::Throw => write!(f, "THROW {} null", self.type_),
+            TokenType::Try => write!(f, "TRY {} null", self.type_),
+            TokenType::Catch => write!(f, "CATCH {} null", self.type_),
+            TokenType::Finally => write!(f, "FINALLY {} null", self.type_),
             TokenType::True => write!(f, "TRUE {} null", self.type_),
             TokenType::Var => write!(f, "VAR {} null", self.type_),
             TokenType::While => write!(f, "WHILE {} null", self.type_),
@@ -191,6 +270,7 @@ impl std::fmt::Display for Token {
             TokenType::Question => write!(f, "QUESTION {} null", self.type_),
             TokenType::Break => write!(f, "BREAK {} null", self.type_),
             TokenType::Eof => write!(f, "EOF  null"),
+            TokenType::Comment => write!(f, "COMMENT {} null", self.lexeme),
         }
     }
 }
@@ -1,45 +1,239 @@
 use core::fmt;
 use std::cell::RefCell;
-use std::ops::{Add, Div, Mul, Sub};
+use std::cmp::Ordering;
+use std::ops::Deref;
 use std::rc::Rc;
-use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::environment::Environment;
 use crate::interpreter::{Interpreter, RuntimeError};
-use crate::stmt::Stmt;
+use crate::stmt::{Param, Stmt};
 use crate::token::Token;
 
 pub type Number = f64;
+pub type Int = i64;
+
+/// A shared, mutable list. A list can end up containing itself, so
+/// comparing and printing lists watch for cycles rather than recursing
+/// forever.
+#[derive(Clone)]
+pub struct List(Rc<RefCell<Vec<LitVal>>>);
+
+impl Deref for List {
+    type Target = RefCell<Vec<LitVal>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// What's being done to a list, for spotting cycles.
+#[derive(Clone, Copy, PartialEq)]
+enum Walk {
+    Eq,
+    Cmp,
+    Show,
+}
+
+thread_local! {
+    /// The lists being compared or printed, as (walk, list, other list).
+    static ACTIVE: RefCell<Vec<(Walk, usize, usize)>> = const { RefCell::new(Vec::new()) };
+}
+
+impl List {
+    /// Runs `f`, or returns `None` if the same walk over the same lists is
+    /// already in progress further up the stack.
+    fn guarded<T>(&self, walk: Walk, other: &List, f: impl FnOnce() -> T) -> Option<T> {
+        let key = (
+            walk,
+            Rc::as_ptr(&self.0) as usize,
+            Rc::as_ptr(&other.0) as usize,
+        );
+        let entered = ACTIVE.with(|active| {
+            let mut active = active.borrow_mut();
+            let entered = !active.contains(&key);
+            if entered {
+                active.push(key);
+            }
+            entered
+        });
+        if !entered {
+            return None;
+        }
+        let result = f();
+        ACTIVE.with(|active| active.borrow_mut().pop());
+        Some(result)
+    }
+
+    /// Whether the lists have the same length and `eq` holds for each pair of
+    /// elements. A pair of lists met again while already being compared is
+    /// assumed equal.
+    pub(crate) fn eq_by(&self, other: &List, eq: impl Fn(&LitVal, &LitVal) -> bool) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+            || self
+                .guarded(Walk::Eq, other, || {
+                    let (items, others) = (self.borrow(), other.borrow());
+                    items.len() == others.len()
+                        && items.iter().zip(others.iter()).all(|(a, b)| eq(a, b))
+                })
+                .unwrap_or(true)
+    }
+}
+
+impl PartialEq for List {
+    /// Lists are equal if their elements are.
+    fn eq(&self, other: &Self) -> bool {
+        self.eq_by(other, LitVal::eq)
+    }
+}
+
+impl PartialOrd for List {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if Rc::ptr_eq(&self.0, &other.0) {
+            return Some(Ordering::Equal);
+        }
+        self.guarded(Walk::Cmp, other, || {
+            self.borrow().partial_cmp(&*other.borrow())
+        })
+        .flatten()
+    }
+}
+
+impl fmt::Display for List {
+    /// Prints a list inside itself as `[...]`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let shown = self.guarded(Walk::Show, self, || {
+            write!(f, "[")?;
+            for (i, item) in self.borrow().iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{}", item)?;
+            }
+            write!(f, "]")
+        });
+        shown.unwrap_or_else(|| write!(f, "[...]"))
+    }
+}
+
+impl fmt::Debug for List {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "List({})", self)
+    }
+}
+
+/// How many arguments a callable accepts; `max` is `None` for variadic functions.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Arity {
+    pub min: usize,
+    pub max: Option<usize>,
+}
+
+impl Arity {
+    pub const fn exact(n: usize) -> Self {
+        Arity {
+            min: n,
+            max: Some(n),
+        }
+    }
+
+    /// The arity of a function with these parameters.
+    pub fn of_params(params: &[Param]) -> Self {
+        Arity {
+            min: params
+                .iter()
+                .filter(|p| p.default.is_none() && !p.rest)
+                .count(),
+            max: if params.iter().any(|p| p.rest) {
+                None
+            } else {
+                Some(params.len())
+            },
+        }
+    }
+
+    pub fn accepts(&self, n: usize) -> bool {
+        n >= self.min && self.max.is_none_or(|max| n <= max)
+    }
+}
+
+impl fmt::Display for Arity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.max {
+            Some(max) if max == self.min => write!(f, "{}", max),
+            Some(max) => write!(f, "{} to {}", self.min, max),
+            None => write!(f, "at least {}", self.min),
+        }
+    }
+}
 
 pub trait LoxCallable {
-    fn arity(&self) -> usize;
+    fn arity(&self) -> Arity;
     fn call(
         &self,
         interpreter: &mut Interpreter,
+        paren: &Token,
         arguments: Vec<LitVal>,
     ) -> Result<LitVal, RuntimeError>;
 }
 
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub enum LitVal {
+    Int(Int),
     Number(Number),
     String(String),
     Bool(bool),
     Nil,
     NotExist,
     Function(LoxFunction),
-    Clock(Clock),
+    Native(NativeFunction),
+    List(List),
+    Module(LoxModule),
+    Error(LoxError),
+}
+
+impl LitVal {
+    pub fn list(items: Vec<LitVal>) -> Self {
+        LitVal::List(List(Rc::new(RefCell::new(items))))
+    }
+
+    /// The name `type()` reports for this value.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            LitVal::Int(_) | LitVal::Number(_) => "number",
+            LitVal::String(_) => "string",
+            LitVal::Bool(_) => "bool",
+            LitVal::Nil | LitVal::NotExist => "nil",
+            LitVal::Function(_) | LitVal::Native(_) => "function",
+            LitVal::List(_) => "list",
+            LitVal::Module(_) => "module",
+            LitVal::Error(_) => "error",
+        }
+    }
+
+    /// Widens either kind of number to a float, for mixed int/float arithmetic.
+    pub fn as_number(&self) -> Option<Number> {
+        match self {
+            LitVal::Int(i) => Some(*i as Number),
+            LitVal::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for LitVal {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            LitVal::Number(n) => write!(f, "{}", n),
+            LitVal::Int(i) => write!(f, "{}", i),
+            // Debug formatting keeps the `.0` on whole floats, so they don't print as ints.
+            LitVal::Number(n) => write!(f, "{:?}", n),
             LitVal::String(s) => write!(f, "{}", s),
             LitVal::Bool(b) => write!(f, "{}", b),
             LitVal::Nil => write!(f, "nil"),
             LitVal::NotExist => write!(f, "not exist"),
-            LitVal::Clock(_) => write!(f, "<native fn>"),
+            LitVal::Native(_) => write!(f, "<native fn>"),
+            LitVal::Module(module) => write!(f, "<module {}>", module.name),
+            LitVal::Error(error) => write!(f, "{}", error.message),
+            LitVal::List(items) => write!(f, "{}", items),
             LitVal::Function(lox_function) => write!(f, "<fn {}>", {
                 match *lox_function.decl {
                     Stmt::Function {
@@ -54,24 +248,71 @@ impl fmt::Display for LitVal {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
-pub struct Clock;
+/// A runtime error caught by `try`, exposing `message` and `line` to Lox code.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct LoxError {
+    pub message: String,
+    pub line: usize,
+}
+
+/// An imported file; its exports are the top-level bindings in `env`.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct LoxModule {
+    pub name: String,
+    pub env: Rc<RefCell<Environment>>,
+}
+
+pub type NativeFn = fn(&mut Interpreter, &Token, Vec<LitVal>) -> Result<LitVal, RuntimeError>;
+
+/// A function implemented in Rust and exposed to Lox code as a global.
+#[derive(Clone)]
+pub struct NativeFunction {
+    pub name: &'static str,
+    arity: Arity,
+    function: NativeFn,
+}
+
+impl NativeFunction {
+    pub fn new(name: &'static str, arity: Arity, function: NativeFn) -> Self {
+        NativeFunction {
+            name,
+            arity,
+            function,
+        }
+    }
+}
+
+impl LoxCallable for NativeFunction {
+    fn arity(&self) -> Arity {
+        self.arity
+    }
 
-impl LoxCallable for Clock {
     fn call(
         &self,
-        _interpreter: &mut Interpreter,
-        _arguments: Vec<LitVal>,
+        interpreter: &mut Interpreter,
+        paren: &Token,
+        arguments: Vec<LitVal>,
     ) -> Result<LitVal, RuntimeError> {
-        let start = SystemTime::now();
-        let since_the_epoch = start
-            .duration_since(UNIX_EPOCH)
-            .expect("Time went backwards");
-        Ok(LitVal::Number(since_the_epoch.as_secs_f64()))
+        (self.function)(interpreter, paren, arguments)
     }
+}
+
+impl fmt::Debug for NativeFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "NativeFunction({})", self.name)
+    }
+}
 
-    fn arity(&self) -> usize {
-        0
+// Natives are registered once under a unique name, so the name identifies them.
+impl PartialEq for NativeFunction {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl PartialOrd for NativeFunction {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.name.partial_cmp(other.name)
     }
 }
 
@@ -85,17 +326,25 @@ impl LoxFunction {
     pub fn new(decl: Rc<Stmt>, closure: Rc<RefCell<Environment>>) -> Self {
         LoxFunction { decl, closure }
     }
+
+    /// The declared name, or `anonymous` for a lambda.
+    pub fn name(&self) -> &str {
+        match &*self.decl {
+            Stmt::Function { name, .. } => &name.lexeme,
+            _ => unreachable!("self.decl should always be a function"),
+        }
+    }
 }
 
 impl LoxCallable for LoxFunction {
-    fn arity(&self) -> usize {
+    fn arity(&self) -> Arity {
         if let Stmt::Function {
             name: _,
             ref params,
             body: _,
         } = *self.decl
         {
-            params.len()
+            Arity::of_params(params)
         } else {
             unreachable!("self.decl should always be a function");
         }
@@ -104,9 +353,10 @@ impl LoxCallable for LoxFunction {
     fn call(
         &self,
         interpreter: &mut Interpreter,
+        _paren: &Token,
         arguments: Vec<LitVal>,
     ) -> Result<LitVal, RuntimeError> {
-        let environment = Rc::new(RefCell::new(Environment::new_with_enclosing(Rc::clone(
+        let _environment = Rc::new(RefCell::new(Environment::new_with_enclosing(Rc::clone(
             &interpreter.globals,
         ))));
 
@@ -119,69 +369,29 @@ impl LoxCallable for LoxFunction {
             let environment = Rc::new(RefCell::new(Environment::new_with_enclosing(Rc::clone(
                 &self.closure,
             ))));
-            for i in 0..params.len() {
+            let mut arguments = arguments.into_iter();
+            for param in params {
+                // Defaults are evaluated per call, and can see the parameters before them.
+                let value = if param.rest {
+                    LitVal::list(arguments.by_ref().collect())
+                } else if let Some(argument) = arguments.next() {
+                    argument
+                } else if let Some(default) = &param.default {
+                    interpreter.evaluate_in(default, Rc::clone(&environment))?
+                } else {
+                    unreachable!("the caller should have checked the arity");
+                };
                 environment
                     .borrow_mut()
-                    .define(params[i].lexeme.clone(), arguments[i].clone());
+                    .define(param.name.lexeme.clone(), value);
+            }
+            match interpreter.exec_block(body, environment) {
+                Ok(_) => Ok(LitVal::Nil),
+                Err(e) if e.is_return() => Ok(e.value.map_or(LitVal::Nil, |value| *value)),
+                Err(e) => Err(e),
             }
-            let _ = match interpreter.exec_block(body, environment) {
-                Ok(n) => Ok::<LitVal, RuntimeError>(n),
-                Err(RuntimeError { message, token }) => {
-                    if message == "return" {
-                        return Ok(token.literal);
-                    } else {
-                        dbg!(token.clone());
-                        return Err(RuntimeError::new(token, &message));
-                    }
-                }
-            };
         } else {
             unreachable!("self.decl should always be a function");
         }
-        Ok(LitVal::Nil)
-    }
-}
-
-impl Sub for LitVal {
-    type Output = Self;
-
-    fn sub(self, other: Self) -> Self {
-        match (self, other) {
-            (LitVal::Number(a), LitVal::Number(b)) => LitVal::Number(a - b),
-            _ => panic!("Subtraction is only supported for numbers"),
-        }
-    }
-}
-impl Div for LitVal {
-    type Output = Self;
-
-    fn div(self, other: Self) -> Self {
-        match (self, other) {
-            (LitVal::Number(a), LitVal::Number(b)) => LitVal::Number(a / b),
-            _ => panic!("Division is only supported for numbers"),
-        }
-    }
-}
-
-impl Mul for LitVal {
-    type Output = Self;
-
-    fn mul(self, other: Self) -> Self {
-        match (self, other) {
-            (LitVal::Number(a), LitVal::Number(b)) => LitVal::Number(a * b),
-            _ => panic!("Multiplication is only supported for numbers"),
-        }
-    }
-}
-
-impl Add for LitVal {
-    type Output = Self;
-
-    fn add(self, other: Self) -> Self {
-        match (self, other) {
-            (LitVal::Number(a), LitVal::Number(b)) => LitVal::Number(a + b),
-            (LitVal::String(a), LitVal::String(b)) => LitVal::String(a + &b),
-            _ => panic!("Addition is only supported for numbers and strings"),
-        }
     }
 }
@@ -0,0 +1,19 @@
+use std::path::Path;
+
+#[test]
+fn golden() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/lox");
+    let report = rustlox::run_tests(&dir).unwrap();
+    for (path, mismatches) in &report.failures {
+        eprintln!("FAIL {}", path.display());
+        for mismatch in mismatches {
+            eprintln!("    {}", mismatch);
+        }
+    }
+    assert!(report.passed > 0);
+    assert!(
+        report.failures.is_empty(),
+        "{} failed",
+        report.failures.len()
+    );
+}